@@ -0,0 +1,13 @@
+#! /bin/bash
+# Builds the canister wasms and candid files into .artifact
+
+set -e
+
+mkdir -p .artifact
+
+for canister in simple_canister mock_evmc; do
+    echo "Building $canister"
+    cargo run -p "$canister" --features "export-api" > ".artifact/$canister.did"
+    cargo build --target wasm32-unknown-unknown --release --package "$canister" --features "export-api"
+    ic-wasm "target/wasm32-unknown-unknown/release/$canister.wasm" -o ".artifact/$canister.wasm" shrink
+done
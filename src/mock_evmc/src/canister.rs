@@ -0,0 +1,174 @@
+use candid::{CandidType, Deserialize};
+use evmc_did::error::EvmError;
+use evmc_did::registration_info::RegistrationInfo;
+use evmc_did::{
+    BasicAccount, Transaction, TransactionParams, TransactionReceipt, H160, H256, U256,
+};
+use ic_canister::{generate_idl, init, query, update, Canister, Idl, PreUpdate};
+use ic_exports::ic_kit::ic;
+use ic_exports::Principal;
+
+use crate::state::{EvmResult, Settings, State, TRANSACTION_GAS};
+
+/// A stand-in for the EVMC canister, implementing the subset of its interface used by
+/// `register-evm-agent` and `simple_canister`.
+///
+/// Balances, nonces, registrations and transactions are kept in memory, and contract
+/// code is never executed.
+#[derive(Canister)]
+pub struct MockEvmcCanister {
+    #[id]
+    id: Principal,
+
+    state: State,
+}
+
+impl PreUpdate for MockEvmcCanister {}
+
+impl MockEvmcCanister {
+    /// Initialize the canister with given data.
+    #[init]
+    pub fn init(&mut self, init_data: InitData) {
+        self.state.reset(Settings {
+            chain_id: init_data.chain_id,
+            minter_address: init_data.minter_address.into(),
+            registration_fee: init_data.registration_fee,
+            min_gas_price: init_data.min_gas_price.into(),
+        });
+    }
+
+    /// Returns the minter address and the fee to pay for registering an IC agent.
+    #[query]
+    pub fn registration_ic_agent_info(&self) -> RegistrationInfo {
+        self.state.registration_info()
+    }
+
+    #[query]
+    pub fn eth_chain_id(&self) -> u64 {
+        self.state.settings().chain_id
+    }
+
+    /// Returns the minimum gas price, hex encoded.
+    #[query]
+    pub fn get_min_gas_price(&self) -> String {
+        format!("{:#x}", self.state.settings().min_gas_price)
+    }
+
+    /// Every transaction uses the same gas, so the estimate doesn't depend on the arguments.
+    #[query]
+    pub fn eth_estimate_gas(
+        &self,
+        _from: String,
+        _to: Option<String>,
+        _gas_limit: u64,
+        _value: String,
+        _data: String,
+    ) -> EvmResult<String> {
+        Ok(format!("{TRANSACTION_GAS:#x}"))
+    }
+
+    #[query]
+    pub fn account_basic(&self, address: H160) -> BasicAccount {
+        self.state.account_basic(address)
+    }
+
+    /// Whether `address` is registered for `principal`.
+    ///
+    /// An address is registered as soon as `register_ic_agent` succeeds, even if the
+    /// registration is not verified yet.
+    #[query]
+    pub fn is_address_registered(&self, address: H160, principal: Principal) -> bool {
+        self.state.is_address_registered(address, principal)
+    }
+
+    /// Registers the sender of the signed registration `transaction` for `principal`.
+    ///
+    /// The signature is verified, and the transaction must pay the registration fee
+    /// to the minter address with the next nonce of the sender.
+    #[update]
+    pub fn register_ic_agent(
+        &mut self,
+        transaction: Transaction,
+        principal: Principal,
+    ) -> EvmResult<()> {
+        self.state.register_ic_agent(transaction, principal)
+    }
+
+    /// Verifies the registration of `principal` with the signing key of the
+    /// registered address.
+    #[update]
+    pub fn verify_registration(
+        &mut self,
+        signing_key: Vec<u8>,
+        principal: Principal,
+    ) -> EvmResult<()> {
+        self.state.verify_registration(&signing_key, principal)
+    }
+
+    /// Mints native tokens to `address`, returning its new balance.
+    #[update]
+    pub fn mint_native_tokens(&mut self, address: H160, amount: U256) -> EvmResult<U256> {
+        self.state.mint(address, amount)
+    }
+
+    /// Same as `mint_native_tokens`.
+    #[update]
+    pub fn mint_evm_tokens(&mut self, address: H160, amount: U256) -> EvmResult<U256> {
+        self.state.mint(address, amount)
+    }
+
+    /// Transfers `params.value` to `to`; the caller must have verified the
+    /// registration of `params.from`.
+    #[update]
+    pub fn call_message(
+        &mut self,
+        params: TransactionParams,
+        to: H160,
+        data: String,
+    ) -> EvmResult<H256> {
+        let data = decode_hex(&data)?;
+        self.state.transact(ic::caller(), params, Some(to), data)
+    }
+
+    /// Transfers `params.value` to the address of the new contract; the caller must
+    /// have verified the registration of `params.from`.
+    #[update]
+    pub fn create_contract(&mut self, params: TransactionParams, code: String) -> EvmResult<H256> {
+        let code = decode_hex(&code)?;
+        self.state.transact(ic::caller(), params, None, code)
+    }
+
+    #[query]
+    pub fn eth_get_transaction_by_hash(&self, hash: H256) -> Option<Transaction> {
+        self.state.transaction(hash)
+    }
+
+    #[query]
+    pub fn eth_get_transaction_receipt(&self, hash: H256) -> EvmResult<Option<TransactionReceipt>> {
+        Ok(self.state.receipt(hash))
+    }
+
+    /// Returns candid IDL.
+    /// This should be the last fn to see previous endpoints in macro.
+    pub fn idl() -> Idl {
+        generate_idl!()
+    }
+}
+
+fn decode_hex(data: &str) -> EvmResult<Vec<u8>> {
+    hex::decode(data.trim_start_matches("0x"))
+        .map_err(|e| EvmError::Internal(format!("invalid hex data: {e}")))
+}
+
+/// Mock EVMC initialization data.
+#[derive(Debug, Deserialize, CandidType, Clone)]
+pub struct InitData {
+    /// Chain id the registration transactions must be signed for.
+    pub chain_id: u64,
+    /// Address the registration fee must be paid to.
+    pub minter_address: H160,
+    /// Minimum value of the registration transactions.
+    pub registration_fee: u64,
+    /// Minimum gas price of the transactions.
+    pub min_gas_price: U256,
+}
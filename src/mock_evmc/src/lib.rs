@@ -0,0 +1,10 @@
+mod canister;
+mod signature;
+mod state;
+
+pub use crate::canister::{InitData, MockEvmcCanister};
+
+pub fn idl() -> String {
+    let idl = MockEvmcCanister::idl();
+    candid::bindings::candid::compile(&idl.env.env, &Some(idl.actor))
+}
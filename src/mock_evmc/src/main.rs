@@ -0,0 +1,3 @@
+fn main() {
+    println!("{}", mock_evmc::idl());
+}
@@ -0,0 +1,107 @@
+use ethers_core::k256::ecdsa::SigningKey;
+use ethers_core::types::Address;
+use ethers_core::utils::secret_key_to_address;
+use evmc_did::error::EvmError;
+use evmc_did::Transaction;
+
+/// Recovers the sender of a signed transaction from its signature.
+///
+/// Fails if the signature is invalid, or if the transaction hash doesn't match
+/// the signed content.
+pub fn recover_sender(transaction: &Transaction) -> Result<Address, EvmError> {
+    let ethers_transaction = ethers_transaction(transaction);
+    if ethers_transaction.hash() != ethers_transaction.hash {
+        return Err(EvmError::TransactionSignature(
+            "the transaction hash doesn't match the signed transaction".to_string(),
+        ));
+    }
+
+    ethers_transaction
+        .recover_from()
+        .map_err(|e| EvmError::TransactionSignature(e.to_string()))
+}
+
+/// Returns the address of a wallet signing key
+pub fn signing_key_address(signing_key: &[u8]) -> Result<Address, EvmError> {
+    let signing_key = SigningKey::from_slice(signing_key)
+        .map_err(|e| EvmError::Internal(format!("invalid signing key: {e}")))?;
+
+    Ok(secret_key_to_address(&signing_key))
+}
+
+/// The conversion to the ethers transaction doesn't keep the EIP-1559 fields,
+/// which are part of the signed content
+pub fn ethers_transaction(transaction: &Transaction) -> ethers_core::types::Transaction {
+    let mut ethers_transaction = ethers_core::types::Transaction::from(transaction.clone());
+    ethers_transaction.max_fee_per_gas = transaction.max_fee_per_gas.clone().map(Into::into);
+    ethers_transaction.max_priority_fee_per_gas =
+        transaction.max_priority_fee_per_gas.clone().map(Into::into);
+    ethers_transaction.transaction_type = transaction.transaction_type.clone().map(Into::into);
+    ethers_transaction
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+
+    use eth_signer::{Signer, Wallet};
+    use ethers_core::types::transaction::eip2718::TypedTransaction;
+    use ethers_core::types::TransactionRequest;
+
+    use super::*;
+
+    /// Legacy transaction signed by `wallet`, with a gas price of 10
+    pub async fn signed_transaction(
+        wallet: &Wallet<'_, SigningKey>,
+        to: Address,
+        value: u64,
+        nonce: u64,
+    ) -> Transaction {
+        let tx: TypedTransaction = TransactionRequest::new()
+            .from(wallet.address())
+            .to(to)
+            .value(value)
+            .chain_id(355113)
+            .nonce(nonce)
+            .gas_price(10)
+            .gas(21000)
+            .into();
+        let signature = wallet.sign_transaction(&tx).await.unwrap();
+        let mut transaction: ethers_core::types::Transaction =
+            rlp::decode(&tx.rlp_signed(&signature)).unwrap();
+        transaction.recover_from_mut().unwrap();
+
+        Transaction::from(transaction)
+    }
+
+    #[tokio::test]
+    async fn should_recover_sender() {
+        let wallet = Wallet::new(&mut rand::thread_rng());
+        let transaction = signed_transaction(&wallet, Address::repeat_byte(1), 100_000, 0).await;
+
+        assert_eq!(recover_sender(&transaction).unwrap(), wallet.address());
+    }
+
+    #[tokio::test]
+    async fn should_reject_tampered_transaction() {
+        let wallet = Wallet::new(&mut rand::thread_rng());
+        let mut transaction =
+            signed_transaction(&wallet, Address::repeat_byte(1), 100_000, 0).await;
+        transaction.value = 1u64.into();
+
+        assert!(matches!(
+            recover_sender(&transaction),
+            Err(EvmError::TransactionSignature(_))
+        ));
+    }
+
+    #[test]
+    fn should_return_signing_key_address() {
+        let wallet = Wallet::new(&mut rand::thread_rng());
+
+        assert_eq!(
+            signing_key_address(&wallet.signer().to_bytes()).unwrap(),
+            wallet.address()
+        );
+        assert!(signing_key_address(&[0; 3]).is_err());
+    }
+}
@@ -0,0 +1,501 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use candid::Principal;
+use ethers_core::types::{Address, TransactionReceipt as EthersReceipt, TxHash, U64};
+use ethers_core::utils::{get_contract_address, keccak256};
+use evmc_did::error::{EvmError, TransactionPoolError};
+use evmc_did::registration_info::RegistrationInfo;
+use evmc_did::{
+    BasicAccount, Transaction, TransactionParams, TransactionReceipt, H160, H256, U256,
+};
+
+use crate::signature::{ethers_transaction, recover_sender, signing_key_address};
+
+/// gas used by every transaction
+pub const TRANSACTION_GAS: u64 = 21_000;
+
+pub type EvmResult<T> = Result<T, EvmError>;
+
+/// State of the mock EVMC.
+///
+/// Everything is kept in memory, so it is lost on upgrade.
+#[derive(Default)]
+pub struct State {}
+
+impl State {
+    /// Clear the state and set the settings.
+    pub fn reset(&mut self, settings: Settings) {
+        EVM_STATE.with(|state| {
+            *state.borrow_mut() = EvmState {
+                settings,
+                ..Default::default()
+            }
+        })
+    }
+
+    /// Returns the state settings.
+    pub fn settings(&self) -> Settings {
+        EVM_STATE.with(|state| state.borrow().settings.clone())
+    }
+
+    /// Returns the minter address and the registration fee.
+    pub fn registration_info(&self) -> RegistrationInfo {
+        let settings = self.settings();
+        RegistrationInfo {
+            minter_address: settings.minter_address.into(),
+            registration_fee: settings.registration_fee,
+        }
+    }
+
+    /// Returns the balance and the nonce of `address`.
+    pub fn account_basic(&self, address: H160) -> BasicAccount {
+        EVM_STATE.with(|state| {
+            let account = state.borrow().account(address.into());
+            BasicAccount {
+                balance: account.balance.into(),
+                nonce: account.nonce.into(),
+            }
+        })
+    }
+
+    /// Adds `amount` to the balance of `address`, returning the new balance.
+    pub fn mint(&mut self, address: H160, amount: U256) -> EvmResult<U256> {
+        EVM_STATE.with(|state| {
+            let mut state = state.borrow_mut();
+            let account = state.accounts.entry(address.into()).or_default();
+            account.balance = account
+                .balance
+                .checked_add(amount.into())
+                .ok_or_else(|| EvmError::Internal("balance overflow".to_string()))?;
+
+            Ok(account.balance.into())
+        })
+    }
+
+    /// Whether `address` is registered for `principal`, even if not verified yet.
+    pub fn is_address_registered(&self, address: H160, principal: Principal) -> bool {
+        EVM_STATE.with(|state| {
+            state
+                .borrow()
+                .registrations
+                .contains_key(&(principal, Address::from(address)))
+        })
+    }
+
+    /// Registers the sender of the signed `transaction` for `principal`.
+    ///
+    /// The transaction must be signed by its sender and pay at least the registration
+    /// fee to the minter address.
+    pub fn register_ic_agent(
+        &mut self,
+        transaction: Transaction,
+        principal: Principal,
+    ) -> EvmResult<()> {
+        let sender = recover_sender(&transaction)?;
+        if sender != Address::from(transaction.from.clone()) {
+            return Err(EvmError::TransactionSignature(format!(
+                "recovered sender {sender:?} doesn't match the transaction sender"
+            )));
+        }
+
+        let settings = self.settings();
+        let chain_id = transaction
+            .chain_id
+            .clone()
+            .map(ethers_core::types::U256::from);
+        if chain_id != Some(settings.chain_id.into()) {
+            return Err(EvmError::TransactionSignature(format!(
+                "invalid chain id {chain_id:?}, expected {}",
+                settings.chain_id
+            )));
+        }
+
+        let to = transaction.to.clone().map(Address::from);
+        let value = ethers_core::types::U256::from(transaction.value.clone());
+        if to != Some(settings.minter_address) || value < settings.registration_fee.into() {
+            return Err(EvmError::RegistrationCheckFailed);
+        }
+
+        EVM_STATE.with(|state| {
+            let mut state = state.borrow_mut();
+            if state.registrations.contains_key(&(principal, sender)) {
+                return Err(EvmError::TransactionPool(
+                    TransactionPoolError::TransactionAlreadyExists,
+                ));
+            }
+            if state
+                .registrations
+                .keys()
+                .any(|(_, address)| *address == sender)
+            {
+                return Err(EvmError::Internal(format!(
+                    "{sender:?} is registered for another principal"
+                )));
+            }
+
+            let gas_price = transaction
+                .max_fee_per_gas
+                .clone()
+                .or_else(|| transaction.gas_price.clone())
+                .map(Into::into)
+                .unwrap_or_default();
+            state.apply(
+                sender,
+                to,
+                value,
+                transaction.nonce.clone().into(),
+                transaction.gas.clone().into(),
+                gas_price,
+            )?;
+
+            let receipt = state.receipt(&ethers_transaction(&transaction), None);
+            state
+                .transactions
+                .insert(transaction.hash.clone().into(), (transaction, receipt));
+            state.registrations.insert((principal, sender), false);
+
+            Ok(())
+        })
+    }
+
+    /// Verifies the registration of `principal` with the signing key of the registered wallet.
+    pub fn verify_registration(
+        &mut self,
+        signing_key: &[u8],
+        principal: Principal,
+    ) -> EvmResult<()> {
+        let address = signing_key_address(signing_key)?;
+
+        EVM_STATE.with(|state| {
+            match state
+                .borrow_mut()
+                .registrations
+                .get_mut(&(principal, address))
+            {
+                Some(verified) => {
+                    *verified = true;
+                    Ok(())
+                }
+                None => Err(EvmError::RegistrationCheckFailed),
+            }
+        })
+    }
+
+    /// Executes a transaction sent by `caller`, which must have verified the registration
+    /// of the sender address.
+    ///
+    /// Contract code is not executed: creating a contract just moves `value` to the
+    /// contract address.
+    pub fn transact(
+        &mut self,
+        caller: Principal,
+        params: TransactionParams,
+        to: Option<H160>,
+        input: Vec<u8>,
+    ) -> EvmResult<H256> {
+        let from = Address::from(params.from.clone());
+        let settings = self.settings();
+
+        EVM_STATE.with(|state| {
+            let mut state = state.borrow_mut();
+            if state.registrations.get(&(caller, from)) != Some(&true) {
+                return Err(EvmError::NotAuthorized);
+            }
+
+            let nonce = ethers_core::types::U256::from(params.nonce.clone());
+            let to = to.map(Address::from);
+            let contract_address = to.is_none().then(|| get_contract_address(from, nonce));
+            let gas_price = params
+                .gas_price
+                .clone()
+                .map(Into::into)
+                .unwrap_or(settings.min_gas_price);
+            state.apply(
+                from,
+                to.or(contract_address),
+                params.value.clone().into(),
+                nonce,
+                params.gas_limit.into(),
+                gas_price,
+            )?;
+
+            let hash = TxHash::from(keccak256(
+                [from.as_bytes(), &nonce.low_u64().to_be_bytes()].concat(),
+            ));
+            let transaction = ethers_core::types::Transaction {
+                hash,
+                nonce,
+                from,
+                to,
+                value: params.value.into(),
+                gas_price: Some(gas_price),
+                gas: params.gas_limit.into(),
+                input: input.into(),
+                chain_id: Some(settings.chain_id.into()),
+                block_number: Some(state.block_number.into()),
+                ..Default::default()
+            };
+            let receipt = state.receipt(&transaction, contract_address);
+            state
+                .transactions
+                .insert(hash, (Transaction::from(transaction), receipt));
+
+            Ok(hash.into())
+        })
+    }
+
+    /// Returns the transaction with the given hash.
+    pub fn transaction(&self, hash: H256) -> Option<Transaction> {
+        EVM_STATE.with(|state| {
+            state
+                .borrow()
+                .transactions
+                .get(&TxHash::from(hash))
+                .map(|(transaction, _)| transaction.clone())
+        })
+    }
+
+    /// Returns the receipt of the transaction with the given hash.
+    pub fn receipt(&self, hash: H256) -> Option<TransactionReceipt> {
+        EVM_STATE.with(|state| {
+            state
+                .borrow()
+                .transactions
+                .get(&TxHash::from(hash))
+                .map(|(_, receipt)| receipt.clone())
+        })
+    }
+}
+
+/// State settings.
+#[derive(Debug, Clone, Default)]
+pub struct Settings {
+    pub chain_id: u64,
+    pub minter_address: Address,
+    pub registration_fee: u64,
+    pub min_gas_price: ethers_core::types::U256,
+}
+
+#[derive(Debug, Clone, Default)]
+struct AccountData {
+    balance: ethers_core::types::U256,
+    nonce: ethers_core::types::U256,
+}
+
+#[derive(Default)]
+struct EvmState {
+    settings: Settings,
+    accounts: HashMap<Address, AccountData>,
+    /// registered (principal, address) pairs, with whether the registration is verified
+    registrations: HashMap<(Principal, Address), bool>,
+    transactions: HashMap<TxHash, (Transaction, TransactionReceipt)>,
+    /// every transaction is mined in its own block
+    block_number: u64,
+}
+
+impl EvmState {
+    fn account(&self, address: Address) -> AccountData {
+        self.accounts.get(&address).cloned().unwrap_or_default()
+    }
+
+    /// Checks the nonce and the balance of `from`, then moves `value` to `to` and charges
+    /// the gas of a transfer
+    fn apply(
+        &mut self,
+        from: Address,
+        to: Option<Address>,
+        value: ethers_core::types::U256,
+        nonce: ethers_core::types::U256,
+        gas_limit: ethers_core::types::U256,
+        gas_price: ethers_core::types::U256,
+    ) -> EvmResult<()> {
+        let min_gas_price = self.settings.min_gas_price;
+        if gas_price < min_gas_price {
+            return Err(EvmError::InvalidGasPrice(format!(
+                "{gas_price} is less than the minimum gas price {min_gas_price}"
+            )));
+        }
+        if gas_limit < TRANSACTION_GAS.into() {
+            return Err(EvmError::Internal(format!(
+                "gas limit {gas_limit} is less than {TRANSACTION_GAS}"
+            )));
+        }
+
+        let account = self.account(from);
+        if nonce != account.nonce {
+            return Err(EvmError::TransactionPool(
+                TransactionPoolError::InvalidNonce {
+                    expected: account.nonce.into(),
+                    actual: nonce.into(),
+                },
+            ));
+        }
+
+        let max_cost = gas_limit.saturating_mul(gas_price).saturating_add(value);
+        if account.balance < max_cost {
+            return Err(EvmError::InsufficientBalance {
+                actual: account.balance.into(),
+                expected: max_cost.into(),
+            });
+        }
+
+        let cost = gas_price * TRANSACTION_GAS + value;
+        let sender = self.accounts.entry(from).or_default();
+        sender.balance -= cost;
+        sender.nonce += ethers_core::types::U256::one();
+        if let Some(to) = to {
+            self.accounts.entry(to).or_default().balance += value;
+        }
+        self.block_number += 1;
+
+        Ok(())
+    }
+
+    /// Receipt of a successful transaction mined in the current block
+    fn receipt(
+        &self,
+        transaction: &ethers_core::types::Transaction,
+        contract_address: Option<Address>,
+    ) -> TransactionReceipt {
+        EthersReceipt {
+            transaction_hash: transaction.hash,
+            from: transaction.from,
+            to: transaction.to,
+            contract_address,
+            block_number: Some(self.block_number.into()),
+            cumulative_gas_used: TRANSACTION_GAS.into(),
+            gas_used: Some(TRANSACTION_GAS.into()),
+            effective_gas_price: transaction.gas_price,
+            status: Some(U64::one()),
+            ..Default::default()
+        }
+        .into()
+    }
+}
+
+thread_local! {
+    static EVM_STATE: RefCell<EvmState> = RefCell::default();
+}
+
+#[cfg(test)]
+mod test {
+
+    use eth_signer::{Signer, Wallet};
+
+    use super::*;
+    use crate::signature::test::signed_transaction;
+
+    const REGISTRATION_FEE: u64 = 100_000;
+
+    fn principal() -> Principal {
+        Principal::from_text("chu2x-jyaaa-aaaah-aaqra-cai").unwrap()
+    }
+
+    fn state() -> State {
+        let mut state = State::default();
+        state.reset(Settings {
+            chain_id: 355113,
+            minter_address: Address::repeat_byte(1),
+            registration_fee: REGISTRATION_FEE,
+            min_gas_price: 10.into(),
+        });
+        state
+    }
+
+    fn params(from: Address, nonce: u64) -> TransactionParams {
+        TransactionParams {
+            from: from.into(),
+            value: 1000u64.into(),
+            gas_limit: TRANSACTION_GAS,
+            gas_price: None,
+            nonce: nonce.into(),
+        }
+    }
+
+    #[tokio::test]
+    async fn should_register_verify_and_transact() {
+        let mut state = state();
+        let wallet = Wallet::new(&mut rand::thread_rng());
+        let address = H160::from(wallet.address());
+        state.mint(address.clone(), 1_000_000u64.into()).unwrap();
+
+        let transaction =
+            signed_transaction(&wallet, Address::repeat_byte(1), REGISTRATION_FEE, 0).await;
+        state
+            .register_ic_agent(transaction.clone(), principal())
+            .unwrap();
+        assert!(state.is_address_registered(address.clone(), principal()));
+        assert!(state.transaction(transaction.hash.clone()).is_some());
+        assert!(matches!(
+            state.register_ic_agent(transaction, principal()),
+            Err(EvmError::TransactionPool(
+                TransactionPoolError::TransactionAlreadyExists
+            ))
+        ));
+
+        // the registration must be verified before transacting
+        assert!(matches!(
+            state.transact(
+                principal(),
+                params(wallet.address(), 1),
+                Some(address.clone()),
+                vec![]
+            ),
+            Err(EvmError::NotAuthorized)
+        ));
+        let other_wallet = Wallet::new(&mut rand::thread_rng());
+        assert!(matches!(
+            state.verify_registration(&other_wallet.signer().to_bytes(), principal()),
+            Err(EvmError::RegistrationCheckFailed)
+        ));
+        state
+            .verify_registration(&wallet.signer().to_bytes(), principal())
+            .unwrap();
+
+        let hash = state
+            .transact(principal(), params(wallet.address(), 1), None, vec![])
+            .unwrap();
+        assert!(state.receipt(hash).unwrap().contract_address.is_some());
+
+        let account = state.account_basic(address);
+        assert_eq!(account.nonce, 2u64.into());
+        assert_eq!(
+            account.balance,
+            (1_000_000 - REGISTRATION_FEE - 1000 - 2 * TRANSACTION_GAS * 10).into()
+        );
+    }
+
+    #[tokio::test]
+    async fn should_reject_invalid_registrations() {
+        let mut state = state();
+        let wallet = Wallet::new(&mut rand::thread_rng());
+        let address = H160::from(wallet.address());
+
+        let transaction =
+            signed_transaction(&wallet, Address::repeat_byte(1), REGISTRATION_FEE, 0).await;
+        assert!(matches!(
+            state.register_ic_agent(transaction.clone(), principal()),
+            Err(EvmError::InsufficientBalance { .. })
+        ));
+
+        state.mint(address.clone(), 1_000_000u64.into()).unwrap();
+        let transaction =
+            signed_transaction(&wallet, Address::repeat_byte(1), REGISTRATION_FEE, 1).await;
+        assert!(matches!(
+            state.register_ic_agent(transaction, principal()),
+            Err(EvmError::TransactionPool(
+                TransactionPoolError::InvalidNonce { .. }
+            ))
+        ));
+
+        let transaction =
+            signed_transaction(&wallet, Address::repeat_byte(2), REGISTRATION_FEE, 0).await;
+        assert!(matches!(
+            state.register_ic_agent(transaction, principal()),
+            Err(EvmError::RegistrationCheckFailed)
+        ));
+
+        assert!(!state.is_address_registered(address, principal()));
+    }
+}
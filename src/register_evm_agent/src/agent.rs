@@ -1,18 +1,48 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 use candid::Principal;
 use ic_agent::agent::http_transport::ReqwestHttpReplicaV2Transport;
 use ic_agent::Agent;
 
+mod dfx;
 mod generic_identity;
+mod key_type;
+mod root_key;
+pub use dfx::DfxIdentities;
 use generic_identity::GenericIdentity;
+pub use key_type::KeyType;
+pub use root_key::RootKey;
 
 use crate::error::{Error, Result};
 
-/// Initialize an IC Agent
-pub async fn init_agent(identity_path: &Path, url: &str) -> Result<Agent> {
-    info!("parsing identity from {}", identity_path.display());
-    let identity = GenericIdentity::try_from(identity_path)?;
+/// PEM encoded identity, with the path it has been read from
+pub struct IdentityPem {
+    pub path: PathBuf,
+    pub pem: Vec<u8>,
+}
+
+impl IdentityPem {
+    /// Read a plaintext PEM file
+    pub fn read(path: &Path) -> Result<Self> {
+        let pem = std::fs::read(path).map_err(|e| Error::Pem(path.to_path_buf(), e.into()))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            pem,
+        })
+    }
+}
+
+/// Initialize an IC Agent; `ingress_expiry` overrides the agent default expiry of update calls,
+/// and `root_key` is the key the replica certificates are verified with
+pub async fn init_agent(
+    identity: &IdentityPem,
+    url: &str,
+    ingress_expiry: Option<Duration>,
+    root_key: &RootKey,
+) -> Result<Agent> {
+    info!("parsing identity from {}", identity.path.display());
+    let identity = GenericIdentity::from_pem(&identity.pem, &identity.path)?;
     info!("identity parsed");
 
     info!("network url: {url}");
@@ -21,10 +51,20 @@ pub async fn init_agent(identity_path: &Path, url: &str) -> Result<Agent> {
     let agent = Agent::builder()
         .with_transport(transport)
         .with_identity(identity)
+        .with_ingress_expiry(ingress_expiry)
         .build()?;
 
-    info!("agent built; fetching root key...");
-    agent.fetch_root_key().await?;
+    match root_key {
+        RootKey::Mainnet => info!("agent built; using the IC mainnet root key"),
+        RootKey::Fetch => {
+            info!("agent built; fetching root key...");
+            agent.fetch_root_key().await?;
+        }
+        RootKey::Pinned(key) => {
+            info!("agent built; using the pinned root key");
+            agent.set_root_key(key.clone())?;
+        }
+    }
     info!("agent initialized");
 
     Ok(agent)
@@ -37,3 +77,45 @@ pub fn user_principal(agent: &Agent) -> Result<Principal> {
         Err(e) => Err(Error::CouldNotGetPrincipal(e)),
     }
 }
+
+/// Checks that the agent identity is a controller of `canister_id`,
+/// reading the canister controllers from the state tree
+pub async fn check_controller(agent: &Agent, canister_id: Principal) -> Result<()> {
+    let principal = user_principal(agent)?;
+    info!("checking that {principal} controls {canister_id}...");
+
+    let controllers = agent
+        .read_state_canister_info(canister_id, "controllers")
+        .await
+        .map_err(|e| Error::CouldNotGetControllers(canister_id, e.to_string()))?;
+    let controllers = decode_controllers(&controllers)
+        .map_err(|e| Error::CouldNotGetControllers(canister_id, e))?;
+
+    if !controllers.contains(&principal) {
+        return Err(Error::NotController(principal, canister_id));
+    }
+
+    Ok(())
+}
+
+/// Decodes the CBOR encoded `controllers` entry of the state tree
+fn decode_controllers(controllers: &[u8]) -> std::result::Result<Vec<Principal>, String> {
+    serde_cbor::from_slice(controllers).map_err(|e| format!("invalid controllers: {e}"))
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    #[test]
+    fn should_decode_controllers() {
+        let controller = Principal::from_slice(&[1, 2, 3, 4, 5, 6, 7, 8, 9, 10]);
+        // self-describe tag, array of one 10 bytes string
+        let mut cbor = vec![0xd9, 0xd9, 0xf7, 0x81, 0x4a];
+        cbor.extend_from_slice(controller.as_slice());
+
+        assert_eq!(decode_controllers(&cbor).unwrap(), vec![controller]);
+        assert!(decode_controllers(&[0x01]).is_err());
+    }
+}
@@ -0,0 +1,228 @@
+use std::path::{Path, PathBuf};
+
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use argon2::{Algorithm, Argon2, Params, PasswordHasher, Version};
+use serde::Deserialize;
+
+use super::IdentityPem;
+use crate::error::{Error, Result};
+
+/// env variable overriding the directory holding the `.config/dfx` directory
+const DFX_CONFIG_ROOT_ENV: &str = "DFX_CONFIG_ROOT";
+/// name of the plaintext identity file
+const IDENTITY_PEM: &str = "identity.pem";
+/// name of the password-encrypted identity file
+const IDENTITY_PEM_ENCRYPTED: &str = "identity.pem.encrypted";
+/// length of the AES-GCM nonce used by dfx
+const NONCE_LENGTH: usize = 12;
+
+/// Identities managed by dfx, stored in `~/.config/dfx/identity/<name>`
+pub struct DfxIdentities {
+    config_dir: PathBuf,
+}
+
+/// `~/.config/dfx/identity.json`
+#[derive(Debug, Deserialize)]
+struct DfxConfiguration {
+    default: String,
+}
+
+/// `~/.config/dfx/identity/<name>/identity.json`
+#[derive(Debug, Deserialize)]
+struct IdentityConfiguration {
+    encryption: Option<EncryptionConfiguration>,
+}
+
+/// Parameters used by dfx to encrypt `identity.pem.encrypted`
+#[derive(Debug, Deserialize)]
+struct EncryptionConfiguration {
+    /// salt of the key derived from the password
+    pw_salt: String,
+    /// AES-GCM nonce
+    file_nonce: Vec<u8>,
+}
+
+impl DfxIdentities {
+    /// Identities of the current user, honouring `DFX_CONFIG_ROOT` as dfx does
+    pub fn new() -> Result<Self> {
+        let root = match std::env::var_os(DFX_CONFIG_ROOT_ENV) {
+            Some(root) => PathBuf::from(root),
+            None => dirs::home_dir().ok_or_else(|| {
+                Error::DfxIdentity(String::new(), "could not find the home directory".into())
+            })?,
+        };
+
+        Ok(Self::with_config_dir(root.join(".config").join("dfx")))
+    }
+
+    /// Identities stored in the given dfx config directory
+    pub fn with_config_dir(config_dir: PathBuf) -> Self {
+        Self { config_dir }
+    }
+
+    /// Returns the name of the dfx default identity
+    pub fn default_identity(&self) -> Result<String> {
+        let path = self.config_dir.join("identity.json");
+        let config: DfxConfiguration =
+            read_json(&path).map_err(|e| Error::DfxIdentity("default".into(), e))?;
+        Ok(config.default)
+    }
+
+    /// Load the identity called `name`, or the default one.
+    /// `password` is called only if the identity is encrypted
+    pub fn load(
+        &self,
+        name: Option<&str>,
+        password: impl FnOnce() -> std::io::Result<String>,
+    ) -> Result<IdentityPem> {
+        let name = match name {
+            Some(name) => name.to_string(),
+            None => self.default_identity()?,
+        };
+        let dir = self.config_dir.join("identity").join(&name);
+        let err = |reason: String| Error::DfxIdentity(name.clone(), reason);
+
+        let plaintext = dir.join(IDENTITY_PEM);
+        if plaintext.exists() {
+            return IdentityPem::read(&plaintext);
+        }
+
+        let encrypted = dir.join(IDENTITY_PEM_ENCRYPTED);
+        if !encrypted.exists() {
+            return Err(err(format!(
+                "no {IDENTITY_PEM} nor {IDENTITY_PEM_ENCRYPTED} in {}; \
+                identities stored in the system keyring are not supported",
+                dir.display()
+            )));
+        }
+
+        let config: IdentityConfiguration = read_json(&dir.join("identity.json")).map_err(&err)?;
+        let encryption = config
+            .encryption
+            .ok_or_else(|| err("identity.json doesn't hold the encryption parameters".into()))?;
+        let content = std::fs::read(&encrypted)
+            .map_err(|e| err(format!("failed to read {}: {e}", encrypted.display())))?;
+        let password = password().map_err(|e| err(format!("failed to read the password: {e}")))?;
+
+        Ok(IdentityPem {
+            path: encrypted,
+            pem: decrypt(&content, &encryption, &password).map_err(err)?,
+        })
+    }
+}
+
+/// Decrypt an `identity.pem.encrypted` file the way dfx encrypts it:
+/// AES-256-GCM with a key derived from the password with Argon2id
+fn decrypt(
+    content: &[u8],
+    encryption: &EncryptionConfiguration,
+    password: &str,
+) -> std::result::Result<Vec<u8>, String> {
+    if encryption.file_nonce.len() != NONCE_LENGTH {
+        return Err(format!(
+            "invalid nonce length {}, expected {NONCE_LENGTH}",
+            encryption.file_nonce.len()
+        ));
+    }
+
+    let key = derive_key(encryption, password)?;
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key));
+    cipher
+        .decrypt(Nonce::from_slice(&encryption.file_nonce), content)
+        .map_err(|_| "decryption failed: wrong password or corrupted file".to_string())
+}
+
+/// Derive the encryption key from the password, with the argon2 parameters used by dfx
+fn derive_key(
+    encryption: &EncryptionConfiguration,
+    password: &str,
+) -> std::result::Result<Vec<u8>, String> {
+    let params = Params::new(64000, 3, 1, Some(32)).map_err(|e| e.to_string())?;
+    let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
+    let hash = argon2
+        .hash_password(password.as_bytes(), &encryption.pw_salt)
+        .map_err(|e| format!("failed to derive the key from the password: {e}"))?;
+    hash.hash
+        .map(|hash| hash.as_bytes().to_vec())
+        .ok_or_else(|| "failed to derive the key from the password".to_string())
+}
+
+fn read_json<T: serde::de::DeserializeOwned>(path: &Path) -> std::result::Result<T, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("failed to read {}: {e}", path.display()))?;
+    serde_json::from_str(&content).map_err(|e| format!("invalid {}: {e}", path.display()))
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    const IDENTITY: &str = "./tests/identity/identity.pem";
+
+    fn config_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(name);
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("identity").join("alice")).unwrap();
+        std::fs::write(dir.join("identity.json"), r#"{ "default": "alice" }"#).unwrap();
+        dir
+    }
+
+    #[test]
+    fn should_load_default_identity() {
+        let dir = config_dir("register_evm_agent_dfx_default");
+        let pem_path = dir.join("identity").join("alice").join(IDENTITY_PEM);
+        std::fs::copy(IDENTITY, &pem_path).unwrap();
+
+        let identities = DfxIdentities::with_config_dir(dir.clone());
+        let pem = identities
+            .load(None, || panic!("identity is not encrypted"))
+            .unwrap();
+        let missing = identities.load(Some("bob"), || panic!("identity is missing"));
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(pem.path, pem_path);
+        assert_eq!(pem.pem, std::fs::read(IDENTITY).unwrap());
+        assert!(matches!(missing, Err(Error::DfxIdentity(name, _)) if name == "bob"));
+    }
+
+    #[test]
+    fn should_load_encrypted_identity() {
+        let dir = config_dir("register_evm_agent_dfx_encrypted");
+        let identity_dir = dir.join("identity").join("alice");
+        let plaintext = std::fs::read(IDENTITY).unwrap();
+
+        let encryption = EncryptionConfiguration {
+            pw_salt: "c2FsdHNhbHRzYWx0c2FsdA".to_string(),
+            file_nonce: vec![7; NONCE_LENGTH],
+        };
+        let key = derive_key(&encryption, "password").unwrap();
+        let encrypted = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key))
+            .encrypt(
+                Nonce::from_slice(&encryption.file_nonce),
+                plaintext.as_slice(),
+            )
+            .unwrap();
+        std::fs::write(identity_dir.join(IDENTITY_PEM_ENCRYPTED), encrypted).unwrap();
+        std::fs::write(
+            identity_dir.join("identity.json"),
+            serde_json::json!({
+                "encryption": {
+                    "pw_salt": encryption.pw_salt,
+                    "file_nonce": encryption.file_nonce,
+                }
+            })
+            .to_string(),
+        )
+        .unwrap();
+
+        let identities = DfxIdentities::with_config_dir(dir.clone());
+        let pem = identities.load(Some("alice"), || Ok("password".into()));
+        let wrong_password = identities.load(Some("alice"), || Ok("not the password".into()));
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(pem.unwrap().pem, plaintext);
+        assert!(wrong_password.is_err());
+    }
+}
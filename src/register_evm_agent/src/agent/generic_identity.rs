@@ -3,7 +3,9 @@ use std::path::Path;
 use candid::Principal;
 use ic_agent::identity::{BasicIdentity, Secp256k1Identity};
 use ic_agent::Identity;
+use ring::signature::Ed25519KeyPair;
 
+use super::key_type::{detect_key, KeyType, PrivateKey};
 use crate::error::Error;
 
 pub enum GenericIdentity {
@@ -15,10 +17,33 @@ impl TryFrom<&Path> for GenericIdentity {
     type Error = Error;
 
     fn try_from(path: &Path) -> Result<Self, Self::Error> {
-        Secp256k1Identity::from_pem_file(path)
-            .map(GenericIdentity::from)
-            .or(BasicIdentity::from_pem_file(path).map(GenericIdentity::from))
-            .map_err(|e| Error::Pem(path.to_path_buf(), e))
+        let pem = std::fs::read(path).map_err(|e| Error::Pem(path.to_path_buf(), e.into()))?;
+        Self::from_pem(&pem, path)
+    }
+}
+
+impl GenericIdentity {
+    /// Parse a PEM or raw DER encoded identity, choosing the identity type from the key type.
+    /// `path` is only used to report errors
+    pub fn from_pem(pem: &[u8], path: &Path) -> Result<Self, Error> {
+        let err = |key_type: KeyType, reason: String| {
+            Error::Identity(path.to_path_buf(), key_type, reason)
+        };
+
+        match detect_key(pem).map_err(|(key_type, reason)| err(key_type, reason))? {
+            PrivateKey::Ed25519(pkcs8) => Ed25519KeyPair::from_pkcs8_maybe_unchecked(&pkcs8)
+                .map(|key_pair| BasicIdentity::from_key_pair(key_pair).into())
+                .map_err(|e| err(KeyType::Ed25519, e.to_string())),
+            PrivateKey::Secp256k1(sec1) => {
+                let pem = pem::encode(&pem::Pem {
+                    tag: "EC PRIVATE KEY".to_string(),
+                    contents: sec1,
+                });
+                Secp256k1Identity::from_pem(pem.as_bytes())
+                    .map(GenericIdentity::from)
+                    .map_err(|e| err(KeyType::Secp256k1, e.to_string()))
+            }
+        }
     }
 }
 
@@ -81,6 +106,56 @@ mod test {
         assert_eq!(expected, principal);
     }
 
+    #[test]
+    fn should_get_ed25519_identity_from_pkcs8_v1_pem() {
+        // PKCS#8 v1, without the public key
+        let mut der = vec![
+            0x30, 0x2e, 0x02, 0x01, 0x00, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x04, 0x22,
+            0x04, 0x20,
+        ];
+        der.extend_from_slice(&[42; 32]);
+        let pem = pem::encode(&pem::Pem {
+            tag: "PRIVATE KEY".to_string(),
+            contents: der,
+        });
+
+        let identity = GenericIdentity::from_pem(pem.as_bytes(), Path::new("v1.pem")).unwrap();
+
+        assert!(matches!(identity, GenericIdentity::BasicIdentity(_)));
+    }
+
+    #[test]
+    fn should_get_ed25519_identity_from_pkcs8_v2_der() {
+        let der = Ed25519KeyPair::generate_pkcs8(&ring::rand::SystemRandom::new()).unwrap();
+
+        let identity = GenericIdentity::from_pem(der.as_ref(), Path::new("v2.der")).unwrap();
+
+        assert!(matches!(identity, GenericIdentity::BasicIdentity(_)));
+    }
+
+    #[test]
+    fn should_report_detected_key_type() {
+        let path = Path::new("./tests/identity/identity.pem");
+        let pem = std::fs::read_to_string(path).unwrap();
+        // corrupt the private key, keeping a valid secp256k1 structure
+        let malformed = pem::parse_many(&pem)
+            .unwrap()
+            .into_iter()
+            .map(|mut block| {
+                if block.tag == "EC PRIVATE KEY" {
+                    block.contents[7..39].fill(0);
+                }
+                pem::encode(&block)
+            })
+            .collect::<String>();
+
+        let err = GenericIdentity::from_pem(malformed.as_bytes(), path)
+            .err()
+            .unwrap();
+
+        assert!(matches!(err, Error::Identity(_, KeyType::Secp256k1, _)));
+    }
+
     #[test]
     fn identity_should_sign() {
         let path = Path::new("./tests/identity/identity.pem");
@@ -0,0 +1,202 @@
+use std::fmt;
+
+/// DER encoded `1.3.101.112`
+const OID_ED25519: &[u8] = &[0x2b, 0x65, 0x70];
+/// DER encoded `1.2.840.10045.2.1`
+const OID_EC_PUBLIC_KEY: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02, 0x01];
+/// DER encoded `1.3.132.0.10`
+const OID_SECP256K1: &[u8] = &[0x2b, 0x81, 0x04, 0x00, 0x0a];
+/// DER encoded `1.2.840.10045.3.1.7`
+const OID_PRIME256V1: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07];
+/// DER encoded `1.2.840.113549.1.1.1`
+const OID_RSA: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x01];
+
+const TAG_INTEGER: u8 = 0x02;
+const TAG_OCTET_STRING: u8 = 0x04;
+const TAG_OID: u8 = 0x06;
+const TAG_SEQUENCE: u8 = 0x30;
+const TAG_EC_PARAMETERS: u8 = 0xa0;
+
+/// Type of the private key held by an identity file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyType {
+    Ed25519,
+    Secp256k1,
+    Prime256v1,
+    Rsa,
+    Unknown,
+}
+
+impl fmt::Display for KeyType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Ed25519 => "Ed25519",
+            Self::Secp256k1 => "secp256k1",
+            Self::Prime256v1 => "P-256",
+            Self::Rsa => "RSA",
+            Self::Unknown => "unknown",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Private key of an identity file, in the encoding expected by its identity type
+#[derive(Debug)]
+pub enum PrivateKey {
+    /// PKCS#8 v1 or v2 DER
+    Ed25519(Vec<u8>),
+    /// SEC1 `ECPrivateKey` DER
+    Secp256k1(Vec<u8>),
+}
+
+/// Detect the key type of a PEM or raw DER identity file, from the PEM label and the key OIDs.
+/// On failure returns the detected key type with the reason
+pub fn detect_key(content: &[u8]) -> Result<PrivateKey, (KeyType, String)> {
+    let start = content
+        .iter()
+        .position(|byte| !byte.is_ascii_whitespace())
+        .unwrap_or(content.len());
+    if !content[start..].starts_with(b"-----BEGIN") {
+        return detect_der(content);
+    }
+
+    let blocks =
+        pem::parse_many(content).map_err(|e| (KeyType::Unknown, format!("invalid PEM: {e}")))?;
+
+    let mut curve = None;
+    for block in &blocks {
+        match block.tag.as_str() {
+            "EC PARAMETERS" => curve = Some(block.contents.as_slice()),
+            "EC PRIVATE KEY" => return sec1(&block.contents, curve),
+            "PRIVATE KEY" => return pkcs8(&block.contents),
+            "RSA PRIVATE KEY" => {
+                return Err((KeyType::Rsa, "RSA keys are not supported".to_string()))
+            }
+            "ENCRYPTED PRIVATE KEY" => {
+                return Err((
+                    KeyType::Unknown,
+                    "encrypted PKCS#8 keys are not supported".to_string(),
+                ))
+            }
+            _ => {}
+        }
+    }
+
+    let labels: Vec<_> = blocks.iter().map(|block| block.tag.as_str()).collect();
+    Err((
+        KeyType::Unknown,
+        format!("no private key found in PEM blocks {labels:?}"),
+    ))
+}
+
+/// Detect the key type of a raw DER file, either PKCS#8 or SEC1
+fn detect_der(der: &[u8]) -> Result<PrivateKey, (KeyType, String)> {
+    let (_, key, _) = read_tlv(der)
+        .filter(|(tag, _, _)| *tag == TAG_SEQUENCE)
+        .ok_or_else(|| {
+            (
+                KeyType::Unknown,
+                "neither a PEM file nor a DER encoded key".to_string(),
+            )
+        })?;
+
+    // the PKCS#8 version is followed by the algorithm sequence, the SEC1 one by the key
+    match read_tlv(key).and_then(|(_, _, rest)| read_tlv(rest)) {
+        Some((TAG_SEQUENCE, _, _)) => pkcs8(der),
+        Some((TAG_OCTET_STRING, _, _)) => sec1(der, None),
+        _ => Err((
+            KeyType::Unknown,
+            "DER key is neither PKCS#8 nor SEC1".to_string(),
+        )),
+    }
+}
+
+/// Detect the key type of a PKCS#8 `PrivateKeyInfo`
+fn pkcs8(der: &[u8]) -> Result<PrivateKey, (KeyType, String)> {
+    let malformed = |what: &str| (KeyType::Unknown, format!("malformed PKCS#8 key: {what}"));
+
+    let (_, info, _) = expect(der, TAG_SEQUENCE).ok_or_else(|| malformed("not a sequence"))?;
+    let (_, rest) = expect(info, TAG_INTEGER).ok_or_else(|| malformed("missing version"))?;
+    let (algorithm, rest) =
+        expect(rest, TAG_SEQUENCE).ok_or_else(|| malformed("missing algorithm"))?;
+    let (oid, params) = expect(algorithm, TAG_OID).ok_or_else(|| malformed("missing OID"))?;
+
+    match oid {
+        OID_ED25519 => Ok(PrivateKey::Ed25519(der.to_vec())),
+        OID_EC_PUBLIC_KEY => {
+            let curve = expect(params, TAG_OID).map(|(curve, _)| curve);
+            let (key, _) = expect(rest, TAG_OCTET_STRING).ok_or_else(|| {
+                (
+                    curve_type(curve),
+                    "malformed PKCS#8 key: missing private key".to_string(),
+                )
+            })?;
+            sec1(key, curve)
+        }
+        OID_RSA => Err((KeyType::Rsa, "RSA keys are not supported".to_string())),
+        oid => Err((
+            KeyType::Unknown,
+            format!("unsupported key algorithm OID {}", hex::encode(oid)),
+        )),
+    }
+}
+
+/// Detect the curve of a SEC1 `ECPrivateKey`; `curve` is the OID from the enclosing structure
+fn sec1(der: &[u8], curve: Option<&[u8]>) -> Result<PrivateKey, (KeyType, String)> {
+    let key_curve = expect(der, TAG_SEQUENCE)
+        .and_then(|(key, _)| expect(key, TAG_INTEGER))
+        .and_then(|(_, rest)| expect(rest, TAG_OCTET_STRING))
+        .and_then(|(_, rest)| expect(rest, TAG_EC_PARAMETERS))
+        .and_then(|(params, _)| expect(params, TAG_OID))
+        .map(|(oid, _)| oid);
+    // PEM `EC PARAMETERS` hold the encoded OID rather than its content
+    let curve = curve.map(|curve| expect(curve, TAG_OID).map_or(curve, |(oid, _)| oid));
+
+    match key_curve.or(curve) {
+        Some(OID_SECP256K1) | None => Ok(PrivateKey::Secp256k1(der.to_vec())),
+        Some(OID_PRIME256V1) => Err((
+            KeyType::Prime256v1,
+            "P-256 keys are not supported".to_string(),
+        )),
+        Some(oid) => Err((
+            KeyType::Unknown,
+            format!("unsupported EC curve OID {}", hex::encode(oid)),
+        )),
+    }
+}
+
+fn curve_type(curve: Option<&[u8]>) -> KeyType {
+    match curve {
+        Some(OID_SECP256K1) => KeyType::Secp256k1,
+        Some(OID_PRIME256V1) => KeyType::Prime256v1,
+        _ => KeyType::Unknown,
+    }
+}
+
+/// Read a DER element with the given tag, returning its content and the remaining input
+fn expect(input: &[u8], tag: u8) -> Option<(&[u8], &[u8])> {
+    read_tlv(input)
+        .filter(|(read_tag, _, _)| *read_tag == tag)
+        .map(|(_, content, rest)| (content, rest))
+}
+
+/// Read a DER element, returning its tag, its content and the remaining input
+fn read_tlv(input: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (&tag, rest) = input.split_first()?;
+    let (&len, rest) = rest.split_first()?;
+    let (len, rest) = if len & 0x80 == 0 {
+        (len as usize, rest)
+    } else {
+        let len_bytes = (len & 0x7f) as usize;
+        if len_bytes == 0 || len_bytes > 4 || rest.len() < len_bytes {
+            return None;
+        }
+        let (len, rest) = rest.split_at(len_bytes);
+        (
+            len.iter().fold(0, |acc, byte| (acc << 8) | *byte as usize),
+            rest,
+        )
+    };
+
+    (rest.len() >= len).then(|| (tag, &rest[..len], &rest[len..]))
+}
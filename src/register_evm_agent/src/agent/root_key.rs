@@ -0,0 +1,64 @@
+use std::path::Path;
+
+use crate::error::{Error, Result};
+
+/// Root key used by the agent to verify the certificates returned by the replica
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RootKey {
+    /// the IC mainnet root key, built into the agent
+    Mainnet,
+    /// the key returned by the replica; it can't be trusted, so only use it on local
+    /// and test networks
+    Fetch,
+    /// a DER encoded key pinned by the user
+    Pinned(Vec<u8>),
+}
+
+impl RootKey {
+    /// Read a DER encoded root key, either raw or hex encoded, from `path`
+    pub fn read(path: &Path) -> Result<Self> {
+        let content =
+            std::fs::read(path).map_err(|e| Error::RootKey(path.to_path_buf(), e.to_string()))?;
+
+        let key = match std::str::from_utf8(&content) {
+            Ok(text) if !text.trim().is_empty() => hex::decode(text.trim())
+                .map_err(|e| Error::RootKey(path.to_path_buf(), format!("invalid hex: {e}")))?,
+            _ => content,
+        };
+        if key.is_empty() {
+            return Err(Error::RootKey(path.to_path_buf(), "empty key".to_string()));
+        }
+
+        Ok(Self::Pinned(key))
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    #[test]
+    fn should_read_hex_and_der_root_keys() {
+        let der = vec![0x30, 0x81, 0x82, 0x30, 0x1d];
+        let hex_path = std::env::temp_dir().join("register_evm_agent_root_key.hex");
+        let der_path = std::env::temp_dir().join("register_evm_agent_root_key.der");
+        std::fs::write(&hex_path, format!("{}\n", hex::encode(&der))).unwrap();
+        std::fs::write(&der_path, &der).unwrap();
+
+        assert_eq!(
+            RootKey::read(&hex_path).unwrap(),
+            RootKey::Pinned(der.clone())
+        );
+        assert_eq!(RootKey::read(&der_path).unwrap(), RootKey::Pinned(der));
+
+        std::fs::write(&hex_path, "not a key").unwrap();
+        assert!(matches!(
+            RootKey::read(&hex_path),
+            Err(Error::RootKey(_, _))
+        ));
+
+        std::fs::remove_file(hex_path).unwrap();
+        std::fs::remove_file(der_path).unwrap();
+    }
+}
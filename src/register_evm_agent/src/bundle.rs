@@ -0,0 +1,145 @@
+use std::path::Path;
+
+use candid::Principal;
+use ethers_core::types::{Address, Bytes};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+use crate::registration::RegistrationTransaction;
+
+/// Portable registration data, produced offline by signing the registration transaction
+/// and consumed online to register the agent.
+///
+/// The bundle never carries the wallet signing key, so it doesn't allow verifying
+/// the registration, which needs it
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RegistrationBundle {
+    /// Principal of the canister to register
+    pub register_canister_id: Principal,
+    /// chain id the transaction was signed for
+    pub chain_id: u64,
+    /// wallet address
+    pub address: Address,
+    /// RLP encoded signed registration transaction
+    pub transaction: Bytes,
+}
+
+impl RegistrationBundle {
+    pub fn new(
+        register_canister_id: Principal,
+        chain_id: u64,
+        tx: &RegistrationTransaction,
+    ) -> Self {
+        Self {
+            register_canister_id,
+            chain_id,
+            address: tx.transaction.from,
+            transaction: tx.rlp.clone(),
+        }
+    }
+
+    /// Read the bundle from a JSON file, checking the transaction was signed by its wallet
+    pub fn read(path: &Path) -> Result<Self> {
+        let bundle_error = |e: String| Error::Bundle(path.to_path_buf(), e);
+
+        let json = std::fs::read_to_string(path).map_err(|e| bundle_error(e.to_string()))?;
+        let bundle: Self = serde_json::from_str(&json).map_err(|e| bundle_error(e.to_string()))?;
+
+        let tx = bundle.registration_transaction()?;
+        if tx.transaction.from != bundle.address {
+            return Err(bundle_error(format!(
+                "transaction is signed by {:?} instead of {:?}",
+                tx.transaction.from, bundle.address
+            )));
+        }
+
+        Ok(bundle)
+    }
+
+    /// Write the bundle to a JSON file
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let bundle_error = |e: String| Error::Bundle(path.to_path_buf(), e);
+
+        let json = serde_json::to_string_pretty(self).map_err(|e| bundle_error(e.to_string()))?;
+        std::fs::write(path, json).map_err(|e| bundle_error(e.to_string()))
+    }
+
+    /// Returns the decoded registration transaction
+    pub fn registration_transaction(&self) -> Result<RegistrationTransaction> {
+        RegistrationTransaction::decode(self.transaction.clone())
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use eth_signer::{Signer, Wallet};
+    use ethers_core::k256::ecdsa::SigningKey;
+
+    use super::*;
+    use crate::gas::TransactionGas;
+    use crate::registration::sign_registration_transaction;
+
+    const CANISTER_ID: &str = "chu2x-jyaaa-aaaah-aaqra-cai";
+    const MINTER_ADDRESS: &str = "0xb0e5863d0ddf7e105e409fee0ecc0123a362e14b";
+    async fn bundle() -> (RegistrationBundle, Wallet<'static, SigningKey>) {
+        let wallet = Wallet::new(&mut rand::thread_rng());
+        let minter_address = MINTER_ADDRESS.parse().unwrap();
+        let tx = sign_registration_transaction(
+            &wallet,
+            minter_address,
+            100_000,
+            0.into(),
+            355113,
+            TransactionGas::default(),
+        )
+        .await
+        .unwrap();
+
+        let bundle =
+            RegistrationBundle::new(Principal::from_text(CANISTER_ID).unwrap(), 355113, &tx);
+        (bundle, wallet)
+    }
+
+    #[tokio::test]
+    async fn should_write_and_read_bundle() {
+        let path = std::env::temp_dir().join("register_evm_agent_bundle_roundtrip.json");
+        let (bundle, wallet) = bundle().await;
+
+        bundle.write(&path).unwrap();
+        let read = RegistrationBundle::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(bundle, read);
+        assert_eq!(read.address, wallet.address());
+        assert_eq!(
+            read.registration_transaction().unwrap().transaction.from,
+            bundle.address
+        );
+    }
+
+    #[tokio::test]
+    async fn should_not_store_the_signing_key() {
+        let path = std::env::temp_dir().join("register_evm_agent_bundle_signing_key.json");
+        let (bundle, wallet) = bundle().await;
+
+        bundle.write(&path).unwrap();
+        let json = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(!json.contains(&hex::encode(wallet.signer().to_bytes())));
+    }
+
+    #[tokio::test]
+    async fn should_reject_bundle_signed_by_another_wallet() {
+        let path = std::env::temp_dir().join("register_evm_agent_bundle_foreign_signer.json");
+        let (mut bundle, _) = bundle().await;
+        bundle.address = Wallet::new(&mut rand::thread_rng()).address();
+
+        bundle.write(&path).unwrap();
+        let read = RegistrationBundle::read(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(matches!(read, Err(Error::Bundle(_, _))));
+    }
+}
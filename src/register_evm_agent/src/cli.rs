@@ -1,27 +1,67 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 use anyhow::Result;
 use candid::Principal;
 use clap::{Args, Parser, Subcommand};
 use eth_signer::{Signer, Wallet};
 use ethers_core::k256::ecdsa::SigningKey;
+use ethers_core::types::Address;
 use evmc_did::H160;
+use ic_agent::Agent;
+use url::Url;
 
+use self::config::Config;
+use self::steps::StepPrinter;
 use super::registration::RegistrationService;
-use crate::agent::init_agent;
+use crate::agent::{check_controller, init_agent, DfxIdentities, IdentityPem, RootKey};
 use crate::error::Error;
+use crate::evmc::AgentEvmcClient;
+use crate::gas::{GasStrategy, TransactionFees};
+use crate::keystore::{decrypt_wallet, encrypt_wallet};
+use crate::mnemonic::{derivation_path, derive_wallet, generate_mnemonic};
+use crate::progress::RegistrationProgress;
+use crate::retry::{RetryPolicy, DEFAULT_INGRESS_EXPIRY};
+
+mod batch;
+mod bundle;
+mod config;
+mod output;
+mod rotate;
+mod status;
+mod steps;
+mod verify;
+
+pub use batch::RegisterBatchArgs;
+pub use bundle::{SignRegistrationArgs, SubmitRegistrationArgs};
+pub use output::{CommandOutput, OutputFormat};
+pub use rotate::RotateArgs;
+pub use status::StatusArgs;
+pub use verify::VerifyArgs;
 
 const DEFAULT_CHAIN_ID: u64 = 355113;
+/// env variable which can hold the keystore password
+const KEYSTORE_PASSWORD_ENV: &str = "REGISTER_EVM_AGENT_KEYSTORE_PASSWORD";
 /// network name for production
 const NETWORK_IC: &str = "ic";
 /// network name for local replica
 const NETWORK_LOCAL: &str = "local";
+/// url of the production network
+const URL_IC: &str = "https://ic0.app";
+/// domains of the IC mainnet boundary nodes
+const IC_DOMAINS: [&str; 3] = ["ic0.app", "icp0.io", "icp-api.io"];
+/// url of the local replica
+const URL_LOCAL: &str = "http://localhost:8000";
 
 /// CLI tool for generating wallet & registering minter principal to the evmc
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 #[command(propagate_version = true)]
 pub struct RegisterMinterCli {
+    /// Output format
+    #[arg(long = "output", global = true, value_enum, default_value_t = OutputFormat::Text)]
+    pub output: OutputFormat,
+
     #[command(subcommand)]
     pub command: Commands,
 }
@@ -29,84 +69,525 @@ pub struct RegisterMinterCli {
 #[derive(Subcommand)]
 pub enum Commands {
     /// Generate an ETH Wallet
-    GenerateWallet,
+    GenerateWallet(GenerateWalletArgs),
 
     /// Register a minter principal to the evmc
     Register(RegisterArgs),
+
+    /// Register all the canisters listed in a manifest file
+    RegisterBatch(RegisterBatchArgs),
+
+    /// Register a new wallet address for a canister which already has one
+    Rotate(RotateArgs),
+
+    /// Show the registration status of a wallet address for a canister
+    Status(StatusArgs),
+
+    /// Sign the registration transaction offline and write it to a registration bundle
+    SignRegistration(SignRegistrationArgs),
+
+    /// Submit a registration bundle created with `sign-registration`
+    SubmitRegistration(SubmitRegistrationArgs),
+
+    /// Run only the verification step of the registration
+    Verify(VerifyArgs),
 }
 
-#[derive(Args)]
-pub struct RegisterArgs {
-    /// amount of native tokens to mint on testnets for this wallet
-    #[arg(short = 'a', long = "amount-to-mint")]
-    pub amount_to_mint: Option<u64>,
+impl Commands {
+    pub async fn exec(&self) -> Result<CommandOutput> {
+        match self {
+            Self::GenerateWallet(generate_wallet_args) => generate_wallet(generate_wallet_args),
+            Self::Register(register_args) => register_args.exec().await,
+            Self::RegisterBatch(register_batch_args) => register_batch_args.exec().await,
+            Self::Rotate(rotate_args) => rotate_args.exec().await,
+            Self::Status(status_args) => status_args.exec().await,
+            Self::SignRegistration(sign_args) => sign_args.exec().await,
+            Self::SubmitRegistration(submit_args) => submit_args.exec().await,
+            Self::Verify(verify_args) => verify_args.exec().await,
+        }
+    }
+}
 
-    /// chain id
-    #[arg(short = 'C', long = "chain-id", default_value_t = DEFAULT_CHAIN_ID)]
-    pub chain_id: u64,
+#[derive(Args)]
+pub struct ConnectionArgs {
+    /// Name of the profile, in the config file, providing the default connection options
+    #[arg(short = 'p', long = "profile")]
+    pub profile: Option<String>,
 
     /// Path to your identity pem file
     #[arg(short = 'i', long = "identity")]
-    pub identity: PathBuf,
+    pub identity: Option<PathBuf>,
+
+    /// Name of the dfx identity to use. If neither this nor `--identity` is set,
+    /// the dfx default identity is used
+    #[arg(long = "dfx-identity", conflicts_with = "identity")]
+    pub dfx_identity: Option<String>,
 
     /// Evmc canister principal
     #[arg(short = 'e', long = "evmc")]
-    pub evmc: Principal,
+    pub evmc: Option<Principal>,
+
+    /// IC Network (ic, local or custom url) [default: local]
+    #[arg(short, long)]
+    pub network: Option<String>,
+
+    /// Path to the DER encoded root key, raw or hex, the replica certificates are verified with
+    #[arg(long = "root-key", conflicts_with = "fetch_root_key")]
+    pub root_key: Option<PathBuf>,
+
+    /// Trust the root key returned by the replica of a custom network. The root key is always
+    /// fetched on the local network and never on the IC mainnet
+    #[arg(long = "fetch-root-key")]
+    pub fetch_root_key: bool,
+
+    /// Maximum number of attempts of a call failing with a transient transport error
+    #[arg(long = "max-attempts", default_value_t = RetryPolicy::default().max_attempts)]
+    pub max_attempts: u32,
+
+    /// Timeout in seconds of every attempt of a call
+    #[arg(long = "timeout")]
+    pub timeout: Option<u64>,
+
+    /// Expiry in seconds of the update calls sent to the replica
+    #[arg(long = "ingress-expiry")]
+    pub ingress_expiry: Option<u64>,
+}
+
+impl ConnectionArgs {
+    /// Merge the command line options with the selected profile; explicit options take precedence
+    pub fn resolve(&self) -> Result<Connection> {
+        let profile = match &self.profile {
+            Some(name) => {
+                let path = Config::path()?;
+                let config = Config::load(&path)?;
+                let profile = config.profile(name).cloned().ok_or_else(|| {
+                    anyhow::anyhow!("profile {name} not found in {}", path.display())
+                })?;
+                Some(profile)
+            }
+            None => None,
+        };
+
+        let identity = match (&self.identity, &self.dfx_identity, &profile) {
+            (None, None, Some(profile)) => {
+                load_identity(profile.identity.as_deref(), profile.dfx_identity.as_deref())?
+            }
+            (identity, dfx_identity, _) => {
+                load_identity(identity.as_deref(), dfx_identity.as_deref())?
+            }
+        };
+
+        let root_key = match (&self.root_key, self.fetch_root_key, &profile) {
+            (None, false, Some(profile)) => profile.root_key.as_deref(),
+            (root_key, _, _) => root_key.as_deref(),
+        }
+        .map(RootKey::read)
+        .transpose()?;
+
+        Ok(Connection {
+            identity,
+            evmc: self.evmc.or(profile.as_ref().and_then(|p| p.evmc)),
+            network: self
+                .network
+                .clone()
+                .or_else(|| profile.as_ref().and_then(|p| p.url.clone()))
+                .unwrap_or_else(|| NETWORK_LOCAL.to_string()),
+            chain_id: profile.as_ref().and_then(|p| p.chain_id),
+            allow_minting: profile.as_ref().map_or(true, |p| p.allow_minting),
+            root_key,
+            fetch_root_key: self.fetch_root_key
+                || profile.as_ref().map_or(false, |p| p.fetch_root_key),
+            ingress_expiry: self.ingress_expiry.map(Duration::from_secs),
+            retry_policy: RetryPolicy {
+                max_attempts: self.max_attempts.max(1),
+                timeout: self.timeout.map(Duration::from_secs),
+                ingress_expiry: self
+                    .ingress_expiry
+                    .map(Duration::from_secs)
+                    .unwrap_or(DEFAULT_INGRESS_EXPIRY),
+                ..Default::default()
+            },
+        })
+    }
+}
+
+/// Connection options, resolved from the command line and the selected profile
+pub struct Connection {
+    identity: IdentityPem,
+    evmc: Option<Principal>,
+    network: String,
+    chain_id: Option<u64>,
+    allow_minting: bool,
+    root_key: Option<RootKey>,
+    fetch_root_key: bool,
+    ingress_expiry: Option<Duration>,
+    retry_policy: RetryPolicy,
+}
+
+impl Connection {
+    /// Evmc canister principal
+    pub fn evmc(&self) -> Result<Principal> {
+        self.evmc
+            .ok_or_else(|| anyhow::anyhow!("no evmc principal set: use --evmc or a profile"))
+    }
+
+    /// Returns `chain_id` if set, otherwise the profile one; when neither is set
+    /// the registration uses the EVMC chain id
+    pub fn chain_id(&self, chain_id: Option<u64>) -> Option<u64> {
+        chain_id.or(self.chain_id)
+    }
+
+    /// Returns `amount_to_mint`, failing if the profile doesn't allow minting
+    pub fn amount_to_mint(&self, amount_to_mint: Option<u64>) -> Result<Option<u64>> {
+        if amount_to_mint.is_some() && !self.allow_minting {
+            anyhow::bail!("minting is not allowed by the selected profile");
+        }
+        Ok(amount_to_mint)
+    }
+
+    /// Retry policy of the calls to the EVMC
+    pub fn retry_policy(&self) -> RetryPolicy {
+        self.retry_policy
+    }
+
+    /// Initialize an IC agent for the selected network and identity
+    pub async fn agent(&self) -> Result<Agent> {
+        self.agent_for(&self.network, self.root_key.as_ref(), self.fetch_root_key)
+            .await
+    }
+
+    /// Initialize an IC agent for the given network with the selected identity;
+    /// the selected root key options belong to the selected network, so the ones
+    /// of `network` are given instead
+    pub async fn agent_for(
+        &self,
+        network: &str,
+        root_key: Option<&RootKey>,
+        fetch_root_key: bool,
+    ) -> Result<Agent> {
+        info!("initializing agent...");
+        let url = network_url(network);
+        let root_key = select_root_key(url, root_key, fetch_root_key)?;
+        Ok(init_agent(&self.identity, url, self.ingress_expiry, &root_key).await?)
+    }
+}
+
+/// Returns the root key for the network at `url`: the built-in one on the IC mainnet,
+/// whatever its boundary node domain, which can't be replaced, otherwise the pinned one
+/// if set, or the fetched one on the local network. On custom networks the root key
+/// must be pinned, or fetched on explicit opt-in
+fn select_root_key(url: &str, pinned: Option<&RootKey>, fetch_root_key: bool) -> Result<RootKey> {
+    if is_mainnet(url) {
+        if pinned.is_some() {
+            anyhow::bail!("the IC mainnet root key is built-in and can't be pinned");
+        }
+        if fetch_root_key {
+            anyhow::bail!("the IC mainnet root key is built-in and can't be fetched");
+        }
+        return Ok(RootKey::Mainnet);
+    }
+
+    match (url, pinned) {
+        (_, Some(root_key)) => Ok(root_key.clone()),
+        (URL_LOCAL, None) => Ok(RootKey::Fetch),
+        (_, None) if fetch_root_key => Ok(RootKey::Fetch),
+        (url, None) => anyhow::bail!(
+            "no root key for network {url}: pin it with --root-key or trust the replica with --fetch-root-key"
+        ),
+    }
+}
+
+/// Whether `url` points to the IC mainnet, through one of its boundary node domains
+/// or a subdomain of them
+fn is_mainnet(url: &str) -> bool {
+    let url = match Url::parse(url) {
+        Ok(url) => url,
+        Err(_) => return false,
+    };
+    let host = match url.host_str() {
+        Some(host) => host.trim_end_matches('.'),
+        None => return false,
+    };
+
+    IC_DOMAINS
+        .iter()
+        .any(|domain| host == *domain || host.ends_with(&format!(".{domain}")))
+}
+
+/// Read the identity pem file, or load the dfx identity (the default one if `dfx_identity` is not
+/// set), prompting for its password if it is encrypted
+fn load_identity(identity: Option<&Path>, dfx_identity: Option<&str>) -> Result<IdentityPem> {
+    if let Some(path) = identity {
+        return Ok(IdentityPem::read(path)?);
+    }
+
+    Ok(DfxIdentities::new()?.load(dfx_identity, || {
+        rpassword::prompt_password("Please enter the passphrase for your identity: ")
+    })?)
+}
+
+#[derive(Args)]
+pub struct GenerateWalletArgs {
+    /// Path of the encrypted keystore file to write the wallet to,
+    /// instead of printing the private key
+    #[arg(long = "keystore")]
+    pub keystore: Option<PathBuf>,
+
+    /// Generate a BIP-39 mnemonic phrase and derive the wallet from it
+    #[arg(long = "mnemonic")]
+    pub mnemonic: bool,
+
+    /// Number of words of the mnemonic phrase (12 or 24)
+    #[arg(long = "words", requires = "mnemonic", default_value_t = 12)]
+    pub words: usize,
+
+    /// Index of the account derived from the mnemonic, at `m/44'/60'/0'/0/<index>`
+    #[arg(long = "index", requires = "mnemonic", default_value_t = 0)]
+    pub index: u32,
+
+    #[command(flatten)]
+    pub password: PasswordArgs,
+}
+
+#[derive(Args)]
+pub struct PasswordArgs {
+    /// Path to a file containing the keystore password. If not set, the password is read
+    /// from the REGISTER_EVM_AGENT_KEYSTORE_PASSWORD env variable or prompted
+    #[arg(long = "password-file")]
+    pub password_file: Option<PathBuf>,
+}
 
-    /// IC Network (ic, local or custom url)
-    #[arg(short, long, default_value_t = String::from(NETWORK_LOCAL))]
-    pub network: String,
+impl PasswordArgs {
+    /// Read the keystore password from the password file, the env or the terminal.
+    /// When prompted, `confirm` asks the password twice
+    fn password(&self, confirm: bool) -> Result<String> {
+        if let Some(path) = &self.password_file {
+            let password = std::fs::read_to_string(path)?;
+            return Ok(password.trim_end_matches(&['\r', '\n'][..]).to_string());
+        }
+
+        if let Ok(password) = std::env::var(KEYSTORE_PASSWORD_ENV) {
+            return Ok(password);
+        }
+
+        let password = rpassword::prompt_password("Keystore password: ")?;
+        if confirm && rpassword::prompt_password("Repeat keystore password: ")? != password {
+            anyhow::bail!("passwords do not match");
+        }
+
+        Ok(password)
+    }
+}
+
+#[derive(Args)]
+pub struct WalletArgs {
+    /// wallet signing key
+    #[arg(
+        short = 'k',
+        long = "key",
+        required_unless_present_any = ["keystore", "mnemonic_file"],
+        conflicts_with_all = ["keystore", "mnemonic_file"]
+    )]
+    pub signing_key: Option<String>,
+
+    /// Path to the encrypted keystore file of the wallet
+    #[arg(long = "keystore", conflicts_with = "mnemonic_file")]
+    pub keystore: Option<PathBuf>,
+
+    /// Path to a file containing the BIP-39 mnemonic phrase the wallet is derived from
+    #[arg(long = "mnemonic-file")]
+    pub mnemonic_file: Option<PathBuf>,
+
+    /// Index of the account derived from the mnemonic, at `m/44'/60'/0'/0/<index>`
+    #[arg(long = "index", requires = "mnemonic_file", default_value_t = 0)]
+    pub index: u32,
+
+    #[command(flatten)]
+    pub password: PasswordArgs,
+}
+
+impl WalletArgs {
+    /// Get the wallet from the signing key, the keystore or the mnemonic
+    pub fn wallet<'a>(&self) -> Result<Wallet<'a, SigningKey>> {
+        match (&self.signing_key, &self.keystore, &self.mnemonic_file) {
+            (Some(signing_key), _, _) => get_wallet(signing_key),
+            (None, Some(keystore), _) => {
+                let password = self.password.password(false)?;
+                Ok(decrypt_wallet(keystore, &password)?)
+            }
+            (None, None, Some(mnemonic_file)) => {
+                let phrase = std::fs::read_to_string(mnemonic_file)?;
+                Ok(derive_wallet(&phrase, self.index)?)
+            }
+            (None, None, None) => {
+                anyhow::bail!("either a signing key, a keystore or a mnemonic must be provided")
+            }
+        }
+    }
+}
+
+#[derive(Args)]
+pub struct GasArgs {
+    /// Gas limit of the registration transaction. If not set, it is estimated by the EVMC
+    #[arg(long = "gas")]
+    pub gas: Option<u64>,
+
+    /// Gas price of the legacy registration transaction. If not set, the EVMC minimum gas price
+    /// is used
+    #[arg(long = "gas-price", conflicts_with = "max_fee_per_gas")]
+    pub gas_price: Option<u64>,
+
+    /// Build an EIP-1559 (type 2) registration transaction with this max fee per gas,
+    /// instead of a legacy one
+    #[arg(long = "max-fee-per-gas")]
+    pub max_fee_per_gas: Option<u64>,
+
+    /// Max priority fee per gas of the EIP-1559 registration transaction
+    #[arg(
+        long = "max-priority-fee-per-gas",
+        requires = "max_fee_per_gas",
+        default_value_t = 0
+    )]
+    pub max_priority_fee_per_gas: u64,
+}
+
+impl GasArgs {
+    /// Returns the gas strategy of the registration transaction
+    pub fn gas_strategy(&self) -> GasStrategy {
+        let fees = match (self.gas_price, self.max_fee_per_gas) {
+            (_, Some(max_fee_per_gas)) => Some(TransactionFees::Eip1559 {
+                max_fee_per_gas: max_fee_per_gas.into(),
+                max_priority_fee_per_gas: self.max_priority_fee_per_gas.into(),
+            }),
+            (Some(gas_price), None) => Some(TransactionFees::Legacy {
+                gas_price: gas_price.into(),
+            }),
+            (None, None) => None,
+        };
+
+        GasStrategy {
+            gas: self.gas.map(Into::into),
+            fees,
+        }
+    }
+}
+
+#[derive(Args)]
+pub struct RegisterArgs {
+    /// maximum amount of native tokens to mint on testnets for this wallet, if its balance
+    /// doesn't cover the registration cost
+    #[arg(short = 'a', long = "amount-to-mint")]
+    pub amount_to_mint: Option<u64>,
+
+    /// chain id [default: the EVMC chain id]
+    #[arg(short = 'C', long = "chain-id")]
+    pub chain_id: Option<u64>,
+
+    #[command(flatten)]
+    pub connection: ConnectionArgs,
 
     /// Principal of the canister to register
     #[arg(short = 'c', long = "canister-id")]
     pub register_canister_id: Principal,
 
-    /// wallet signing key
-    #[arg(short = 'k', long = "key")]
-    pub signing_key: String,
+    #[command(flatten)]
+    pub wallet: WalletArgs,
+
+    #[command(flatten)]
+    pub gas: GasArgs,
+
+    /// Build and sign the registration transaction and print it, without submitting anything
+    #[arg(long = "dry-run")]
+    pub dry_run: bool,
+
+    /// Do not resume a previous registration attempt nor save the registration progress
+    #[arg(long = "no-progress")]
+    pub no_progress: bool,
+
+    /// Proceed, with a warning, even if the identity is not a controller of the canister
+    #[arg(long = "force")]
+    pub force: bool,
+
+    /// Print each registration step to stderr as it completes
+    #[arg(long = "show-steps")]
+    pub show_steps: bool,
 }
 
 impl RegisterArgs {
-    pub async fn exec(&self) -> Result<()> {
-        let wallet = get_wallet(self.signing_key.as_str())?;
+    pub async fn exec(&self) -> Result<CommandOutput> {
+        let wallet = self.wallet.wallet()?;
         let address = wallet.address();
 
-        info!("initializing agent...");
-        let network = network_url(&self.network);
-        let agent = init_agent(&self.identity, network).await?;
-
-        match RegistrationService::new(
-            agent,
-            self.amount_to_mint,
-            self.chain_id,
-            self.evmc,
-            self.register_canister_id,
-            wallet,
-        )
-        .await
-        .map_err(|e| anyhow::anyhow!("{e}"))?
-        .register()
-        .await
-        {
-            Ok(()) => {
-                println!(
-                    "Registration succeeded:\n  Wallet Address = {}\n  Principal = {}",
-                    H160::from(address).to_hex_str(),
-                    self.register_canister_id
-                );
-
-                Ok(())
-            }
-            Err(Error::AlreadyRegistered(principal)) => {
-                println!(
-                    "Already registered:\n\tWallet Address = {}\n\tPrincipal = {}",
-                    H160::from(address).to_hex_str(),
-                    principal
-                );
-                Ok(())
-            }
-            Err(err) => anyhow::bail!("{err}"),
+        let connection = self.connection.resolve()?;
+        let evmc = connection.evmc()?;
+        let amount_to_mint = connection.amount_to_mint(self.amount_to_mint)?;
+        let agent = connection.agent().await?;
+        if !self.dry_run {
+            ensure_controller(&agent, self.register_canister_id, self.force).await?;
+        }
+
+        let mut builder = RegistrationService::builder()
+            .evmc(AgentEvmcClient::new(agent, evmc))
+            .target_canister(self.register_canister_id)
+            .wallet(wallet)
+            .amount_to_mint(amount_to_mint)
+            .gas_strategy(self.gas.gas_strategy())
+            .retry_policy(connection.retry_policy());
+        if let Some(chain_id) = connection.chain_id(self.chain_id) {
+            builder = builder.chain_id(chain_id);
+        }
+        if self.show_steps {
+            builder = builder.observer(StepPrinter);
+        }
+        let registration_service = builder.build().await?;
+
+        if self.dry_run {
+            let tx = registration_service.registration_transaction().await?;
+            return Ok(CommandOutput::registration_transaction(&tx, None));
+        }
+
+        let result = if self.no_progress {
+            registration_service.register().await
+        } else {
+            let mut progress = RegistrationProgress::load(
+                &progress_dir()?,
+                evmc,
+                self.register_canister_id,
+                address,
+            )?;
+            info!("registration progress: {}", progress.path().display());
+            registration_service
+                .resume_registration(&mut progress)
+                .await
+        };
+        registration_output(result, address, self.register_canister_id)
+    }
+}
+
+/// Returns the output for the outcome of a registration
+fn registration_output(
+    result: std::result::Result<(), Error>,
+    address: Address,
+    register_canister_id: Principal,
+) -> Result<CommandOutput> {
+    match result {
+        Ok(()) => Ok(CommandOutput::registered(address, register_canister_id)),
+        Err(Error::AlreadyRegistered(principal)) => {
+            Ok(CommandOutput::already_registered(address, principal))
+        }
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Checks that the identity is a controller of the canister to register,
+/// only warning if the check fails and `force` is set
+async fn ensure_controller(agent: &Agent, canister_id: Principal, force: bool) -> Result<()> {
+    match check_controller(agent, canister_id).await {
+        Err(err) if force => {
+            warn!("{err}; proceeding because of --force");
+            Ok(())
         }
+        result => Ok(result?),
     }
 }
 
@@ -118,28 +599,94 @@ fn get_wallet<'a>(signing_key: &str) -> Result<Wallet<'a, SigningKey>> {
 }
 
 /// generate a brand new wallet
-pub fn generate_wallet<'a>() -> Result<Wallet<'a, SigningKey>> {
-    let mut rng = rand::thread_rng();
-    let wallet = Wallet::new(&mut rng);
-    let signer = wallet.signer();
-    let signer_hex = hex::encode(signer.to_bytes());
+pub fn generate_wallet(args: &GenerateWalletArgs) -> Result<CommandOutput> {
+    let (wallet, mnemonic) = if args.mnemonic {
+        let phrase = generate_mnemonic(args.words)?;
+        (derive_wallet(&phrase, args.index)?, Some(phrase))
+    } else {
+        let mut rng = rand::thread_rng();
+        (Wallet::new(&mut rng), None)
+    };
     let public_key = wallet.signer().verifying_key();
     let public_key_hex = hex::encode(public_key.to_sec1_bytes());
     let address: H160 = wallet.address().into();
-    println!(
-        "Wallet:\n  Private Key = {}\n  Public Key = {}\n  Address = {}",
-        signer_hex,
-        public_key_hex,
-        address.to_hex_str(),
-    );
-    Ok(wallet)
+
+    let private_key = if let Some(keystore) = &args.keystore {
+        let password = args.password.password(true)?;
+        encrypt_wallet(keystore, &wallet, &password)?;
+        None
+    } else {
+        Some(hex::encode(wallet.signer().to_bytes()))
+    };
+
+    Ok(CommandOutput::Wallet {
+        address: address.to_hex_str(),
+        public_key: public_key_hex,
+        private_key,
+        keystore: args.keystore.clone(),
+        derivation_path: mnemonic.as_ref().map(|_| derivation_path(args.index)),
+        mnemonic,
+    })
+}
+
+/// directory where the registration progress records are saved
+fn progress_dir() -> Result<PathBuf> {
+    let data_dir = dirs::data_local_dir()
+        .ok_or_else(|| anyhow::anyhow!("could not find the local data directory"))?;
+    Ok(data_dir.join("register-evm-agent").join("progress"))
 }
 
 /// make network url from network name
 fn network_url(network: &str) -> &str {
     match network {
-        NETWORK_LOCAL => "http://localhost:8000",
-        NETWORK_IC => "https://ic0.app",
+        NETWORK_LOCAL => URL_LOCAL,
+        NETWORK_IC => URL_IC,
         url => url,
     }
 }
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    #[test]
+    fn should_select_the_root_key_of_the_network() {
+        let pinned = RootKey::Pinned(vec![1, 2, 3]);
+        let custom = "https://testnet.example.org";
+
+        // (url, pinned key, fetch root key, expected key; None if rejected)
+        let cases = [
+            (URL_IC, None, false, Some(RootKey::Mainnet)),
+            (URL_IC, None, true, None),
+            (URL_IC, Some(pinned.clone()), false, None),
+            ("https://icp0.io", None, false, Some(RootKey::Mainnet)),
+            ("https://icp0.io", None, true, None),
+            ("https://ic0.app/", Some(pinned.clone()), false, None),
+            ("https://icp-api.io", None, true, None),
+            (
+                "https://a4gq6-oaaaa-aaaab-qaa4q-cai.raw.ic0.app",
+                None,
+                true,
+                None,
+            ),
+            (
+                "https://ic0.app.example.org",
+                None,
+                true,
+                Some(RootKey::Fetch),
+            ),
+            (URL_LOCAL, None, false, Some(RootKey::Fetch)),
+            (URL_LOCAL, None, true, Some(RootKey::Fetch)),
+            (URL_LOCAL, Some(pinned.clone()), false, Some(pinned.clone())),
+            (custom, None, false, None),
+            (custom, None, true, Some(RootKey::Fetch)),
+            (custom, Some(pinned.clone()), false, Some(pinned.clone())),
+        ];
+
+        for (url, root_key, fetch_root_key, expected) in cases {
+            let root_key = select_root_key(url, root_key.as_ref(), fetch_root_key).ok();
+            assert_eq!(root_key, expected, "{url}, fetch: {fetch_root_key}");
+        }
+    }
+}
@@ -0,0 +1,293 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use candid::Principal;
+use clap::Args;
+use eth_signer::{Signer, Wallet};
+use ethers_core::k256::ecdsa::SigningKey;
+use ethers_core::types::Address;
+use evmc_did::H160;
+use futures::StreamExt;
+use serde::Deserialize;
+
+use super::output::{BatchEntryOutput, BatchOutcome};
+use super::{
+    ensure_controller, get_wallet, progress_dir, CommandOutput, Connection, ConnectionArgs,
+    GasArgs, PasswordArgs,
+};
+use crate::agent::RootKey;
+use crate::error::Error;
+use crate::evmc::AgentEvmcClient;
+use crate::keystore::decrypt_wallet;
+use crate::progress::RegistrationProgress;
+use crate::registration::RegistrationService;
+
+#[derive(Args)]
+pub struct RegisterBatchArgs {
+    /// Path to the manifest file (toml, yaml or json) listing the canisters to register
+    #[arg(short = 'm', long = "manifest")]
+    pub manifest: PathBuf,
+
+    /// Connection options, used for the entries which don't override them
+    #[command(flatten)]
+    pub connection: ConnectionArgs,
+
+    /// chain id [default: the EVMC chain id]
+    #[arg(short = 'C', long = "chain-id")]
+    pub chain_id: Option<u64>,
+
+    #[command(flatten)]
+    pub gas: GasArgs,
+
+    /// maximum number of registrations running at the same time
+    #[arg(long = "concurrency", default_value_t = 4)]
+    pub concurrency: usize,
+
+    /// Proceed, with a warning, even if the identity is not a controller of the canister
+    #[arg(long = "force")]
+    pub force: bool,
+
+    #[command(flatten)]
+    pub password: PasswordArgs,
+}
+
+/// Canisters to register
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Manifest {
+    canisters: Vec<ManifestEntry>,
+}
+
+/// A canister to register, with the wallet to register it with
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ManifestEntry {
+    /// Principal of the canister to register
+    canister_id: Principal,
+    /// wallet signing key
+    key: Option<String>,
+    /// Path to the encrypted keystore file of the wallet, relative to the manifest
+    keystore: Option<PathBuf>,
+    /// maximum amount of native tokens to mint on testnets for this wallet
+    amount_to_mint: Option<u64>,
+    /// IC Network, overriding the command one
+    network: Option<String>,
+    /// Path to the root key of `network`, relative to the manifest; the command root key
+    /// options only apply to the command network
+    root_key: Option<PathBuf>,
+    /// Whether to trust the root key returned by the replica of `network`
+    #[serde(default)]
+    fetch_root_key: bool,
+    /// Evmc canister principal, overriding the command one
+    evmc: Option<Principal>,
+}
+
+impl RegisterBatchArgs {
+    pub async fn exec(&self) -> Result<CommandOutput> {
+        let manifest = read_manifest(&self.manifest)?;
+        let manifest_dir = self.manifest.parent().unwrap_or_else(|| Path::new("."));
+        let connection = self.connection.resolve()?;
+
+        // the password is read once, before the registrations start
+        let password = if manifest.canisters.iter().any(|e| e.keystore.is_some()) {
+            Some(self.password.password(false)?)
+        } else {
+            None
+        };
+
+        let entries: Vec<_> = futures::stream::iter(&manifest.canisters)
+            .map(|entry| async {
+                let (address, outcome) = match self
+                    .register_entry(&connection, entry, manifest_dir, password.as_deref())
+                    .await
+                {
+                    Ok((address, outcome)) => (Some(address), outcome),
+                    Err(e) => (None, BatchOutcome::Failed { error: (&e).into() }),
+                };
+                BatchEntryOutput {
+                    canister_id: entry.canister_id,
+                    wallet_address: address.map(|address| H160::from(address).to_hex_str()),
+                    outcome,
+                }
+            })
+            .buffered(self.concurrency.max(1))
+            .collect()
+            .await;
+
+        let failures = entries
+            .iter()
+            .filter(|entry| matches!(entry.outcome, BatchOutcome::Failed { .. }))
+            .count();
+
+        Ok(CommandOutput::Batch { entries, failures })
+    }
+
+    /// Register a single manifest entry.
+    /// A wallet address can be returned together with a failed outcome
+    async fn register_entry(
+        &self,
+        connection: &Connection,
+        entry: &ManifestEntry,
+        manifest_dir: &Path,
+        password: Option<&str>,
+    ) -> Result<(Address, BatchOutcome)> {
+        let wallet = entry_wallet(entry, manifest_dir, password)?;
+        let address = wallet.address();
+        let evmc = match entry.evmc {
+            Some(evmc) => evmc,
+            None => connection.evmc()?,
+        };
+
+        let outcome = match self
+            .register_wallet(connection, entry, manifest_dir, evmc, wallet)
+            .await
+        {
+            Ok(()) => BatchOutcome::Registered,
+            Err(e) => match e.downcast_ref::<Error>() {
+                Some(Error::AlreadyRegistered(_)) => BatchOutcome::AlreadyRegistered,
+                _ => BatchOutcome::Failed { error: (&e).into() },
+            },
+        };
+
+        Ok((address, outcome))
+    }
+
+    async fn register_wallet(
+        &self,
+        connection: &Connection,
+        entry: &ManifestEntry,
+        manifest_dir: &Path,
+        evmc: Principal,
+        wallet: Wallet<'_, SigningKey>,
+    ) -> Result<()> {
+        let address = wallet.address();
+        let amount_to_mint = connection.amount_to_mint(entry.amount_to_mint)?;
+        let agent = match &entry.network {
+            Some(network) => {
+                let root_key = entry
+                    .root_key
+                    .as_ref()
+                    .map(|path| RootKey::read(&manifest_dir.join(path)))
+                    .transpose()?;
+                connection
+                    .agent_for(network, root_key.as_ref(), entry.fetch_root_key)
+                    .await?
+            }
+            None if entry.root_key.is_some() || entry.fetch_root_key => {
+                anyhow::bail!("`root_key` and `fetch_root_key` can only be set with `network`")
+            }
+            None => connection.agent().await?,
+        };
+        ensure_controller(&agent, entry.canister_id, self.force).await?;
+
+        let mut progress =
+            RegistrationProgress::load(&progress_dir()?, evmc, entry.canister_id, address)?;
+
+        let mut builder = RegistrationService::builder()
+            .evmc(AgentEvmcClient::new(agent, evmc))
+            .target_canister(entry.canister_id)
+            .wallet(wallet)
+            .amount_to_mint(amount_to_mint)
+            .gas_strategy(self.gas.gas_strategy())
+            .retry_policy(connection.retry_policy());
+        if let Some(chain_id) = connection.chain_id(self.chain_id) {
+            builder = builder.chain_id(chain_id);
+        }
+
+        builder
+            .build()
+            .await?
+            .resume_registration(&mut progress)
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// Read the manifest, choosing the format from the file extension
+fn read_manifest(path: &Path) -> Result<Manifest> {
+    let content = std::fs::read_to_string(path)?;
+    let manifest = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("toml") => toml::from_str(&content)?,
+        Some("yaml") | Some("yml") => serde_yaml::from_str(&content)?,
+        Some("json") => serde_json::from_str(&content)?,
+        _ => anyhow::bail!(
+            "unsupported manifest format {}: expected a toml, yaml or json file",
+            path.display()
+        ),
+    };
+
+    Ok(manifest)
+}
+
+/// Get the wallet of a manifest entry either from its signing key or from its keystore
+fn entry_wallet<'a>(
+    entry: &ManifestEntry,
+    manifest_dir: &Path,
+    password: Option<&str>,
+) -> Result<Wallet<'a, SigningKey>> {
+    match (&entry.key, &entry.keystore, password) {
+        (Some(key), None, _) => get_wallet(key),
+        (None, Some(keystore), Some(password)) => {
+            Ok(decrypt_wallet(&manifest_dir.join(keystore), password)?)
+        }
+        _ => anyhow::bail!("exactly one of `key` and `keystore` must be set"),
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    #[test]
+    fn should_parse_manifest_formats() {
+        let toml = r#"
+            [[canisters]]
+            canister_id = "chu2x-jyaaa-aaaah-aaqra-cai"
+            keystore = "wallets/simple_canister.json"
+            amount_to_mint = 1000000
+
+            [[canisters]]
+            canister_id = "4fe7g-7iaaa-aaaak-aegcq-cai"
+            key = "048f4682aa84d9c92f4452956896e459a5d8b675895ca0a7dca6028641256c12"
+            network = "ic"
+        "#;
+        let yaml = r#"
+            canisters:
+              - canister_id: chu2x-jyaaa-aaaah-aaqra-cai
+                keystore: wallets/simple_canister.json
+                amount_to_mint: 1000000
+              - canister_id: 4fe7g-7iaaa-aaaak-aegcq-cai
+                key: "048f4682aa84d9c92f4452956896e459a5d8b675895ca0a7dca6028641256c12"
+                network: ic
+        "#;
+
+        let from_toml: Manifest = toml::from_str(toml).unwrap();
+        let from_yaml: Manifest = serde_yaml::from_str(yaml).unwrap();
+
+        for manifest in [from_toml, from_yaml] {
+            assert_eq!(manifest.canisters.len(), 2);
+            assert_eq!(manifest.canisters[0].amount_to_mint, Some(1000000));
+            assert!(manifest.canisters[0].keystore.is_some());
+            assert_eq!(manifest.canisters[1].network.as_deref(), Some("ic"));
+            assert!(manifest.canisters[1].key.is_some());
+        }
+    }
+
+    #[test]
+    fn should_reject_entry_with_both_key_sources() {
+        let entry = ManifestEntry {
+            canister_id: Principal::anonymous(),
+            key: Some("048f4682aa84d9c92f4452956896e459a5d8b675895ca0a7dca6028641256c12".into()),
+            keystore: Some("wallet.json".into()),
+            amount_to_mint: None,
+            network: None,
+            root_key: None,
+            fetch_root_key: false,
+            evmc: None,
+        };
+
+        assert!(entry_wallet(&entry, Path::new("."), Some("password")).is_err());
+    }
+}
@@ -0,0 +1,124 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+use candid::Principal;
+use clap::Args;
+use ethers_core::types::Address;
+
+use super::{
+    ensure_controller, CommandOutput, ConnectionArgs, GasArgs, WalletArgs, DEFAULT_CHAIN_ID,
+};
+use crate::bundle::RegistrationBundle;
+use crate::error::Error;
+use crate::evmc::AgentEvmcClient;
+use crate::registration::{sign_registration_transaction, RegistrationService};
+
+#[derive(Args)]
+pub struct SignRegistrationArgs {
+    #[command(flatten)]
+    pub wallet: WalletArgs,
+
+    #[command(flatten)]
+    pub gas: GasArgs,
+
+    /// Principal of the canister to register
+    #[arg(short = 'c', long = "canister-id")]
+    pub register_canister_id: Principal,
+
+    /// Registration fee, as returned by `registration_ic_agent_info`
+    #[arg(short = 'f', long = "fee")]
+    pub registration_fee: u64,
+
+    /// Minter address, as returned by `registration_ic_agent_info`
+    #[arg(short = 'm', long = "minter-address")]
+    pub minter_address: Address,
+
+    /// Nonce of the wallet
+    #[arg(long = "nonce", default_value_t = 0)]
+    pub nonce: u64,
+
+    /// chain id
+    #[arg(short = 'C', long = "chain-id", default_value_t = DEFAULT_CHAIN_ID)]
+    pub chain_id: u64,
+
+    /// Path of the registration bundle to write
+    #[arg(short = 'b', long = "bundle")]
+    pub bundle: PathBuf,
+}
+
+impl SignRegistrationArgs {
+    pub async fn exec(&self) -> Result<CommandOutput> {
+        let wallet = self.wallet.wallet()?;
+
+        let tx = sign_registration_transaction(
+            &wallet,
+            self.minter_address,
+            self.registration_fee,
+            self.nonce.into(),
+            self.chain_id,
+            self.gas.gas_strategy().offline(),
+        )
+        .await?;
+
+        RegistrationBundle::new(self.register_canister_id, self.chain_id, &tx)
+            .write(&self.bundle)?;
+
+        Ok(CommandOutput::registration_transaction(
+            &tx,
+            Some(self.bundle.as_path()),
+        ))
+    }
+}
+
+#[derive(Args)]
+pub struct SubmitRegistrationArgs {
+    #[command(flatten)]
+    pub connection: ConnectionArgs,
+
+    /// maximum amount of native tokens to mint on testnets for this wallet, if its balance
+    /// doesn't cover the registration cost
+    #[arg(short = 'a', long = "amount-to-mint")]
+    pub amount_to_mint: Option<u64>,
+
+    /// Path of the registration bundle created with `sign-registration`
+    #[arg(short = 'b', long = "bundle")]
+    pub bundle: PathBuf,
+
+    /// Proceed, with a warning, even if the identity is not a controller of the canister
+    #[arg(long = "force")]
+    pub force: bool,
+}
+
+impl SubmitRegistrationArgs {
+    pub async fn exec(&self) -> Result<CommandOutput> {
+        let bundle = RegistrationBundle::read(&self.bundle)?;
+        let tx = bundle.registration_transaction()?;
+
+        let connection = self.connection.resolve()?;
+        let amount_to_mint = connection.amount_to_mint(self.amount_to_mint)?;
+        let agent = connection.agent().await?;
+        ensure_controller(&agent, bundle.register_canister_id, self.force).await?;
+
+        let registration_service = RegistrationService::builder()
+            .evmc(AgentEvmcClient::new(agent, connection.evmc()?))
+            .target_canister(bundle.register_canister_id)
+            .chain_id(bundle.chain_id)
+            .address(bundle.address)
+            .amount_to_mint(amount_to_mint)
+            .retry_policy(connection.retry_policy())
+            .build()
+            .await?;
+
+        // the verification needs the wallet signing key, which is not in the bundle
+        match registration_service.submit_registration(tx).await {
+            Ok(()) => Ok(CommandOutput::agent_registered(
+                bundle.address,
+                bundle.register_canister_id,
+            )),
+            Err(Error::AlreadyRegistered(principal)) => {
+                Ok(CommandOutput::already_registered(bundle.address, principal))
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+}
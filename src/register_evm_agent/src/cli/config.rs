@@ -0,0 +1,102 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use candid::Principal;
+use serde::Deserialize;
+
+/// User configuration, read from `register-evm-agent/config.toml` in the platform config dir
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// named network profiles
+    #[serde(default)]
+    profiles: HashMap<String, Profile>,
+}
+
+/// Default options for a network
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    /// IC Network (ic, local or custom url)
+    pub url: Option<String>,
+    /// Evmc canister principal
+    pub evmc: Option<Principal>,
+    /// chain id
+    pub chain_id: Option<u64>,
+    /// Path to the identity pem file
+    pub identity: Option<PathBuf>,
+    /// Name of the dfx identity, used if `identity` is not set
+    pub dfx_identity: Option<String>,
+    /// whether native tokens can be minted on this network
+    #[serde(default)]
+    pub allow_minting: bool,
+    /// Path to the DER encoded root key of the network, raw or hex
+    pub root_key: Option<PathBuf>,
+    /// whether the root key returned by the replica of a custom network is trusted
+    #[serde(default)]
+    pub fetch_root_key: bool,
+}
+
+impl Config {
+    /// Returns the path of the config file
+    pub fn path() -> Result<PathBuf> {
+        let config_dir = dirs::config_dir()
+            .ok_or_else(|| anyhow::anyhow!("could not find the config directory"))?;
+        Ok(config_dir.join("register-evm-agent").join("config.toml"))
+    }
+
+    /// Read the config file at `path`
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("failed to read config file {}: {e}", path.display()))?;
+        toml::from_str(&content)
+            .map_err(|e| anyhow::anyhow!("invalid config file {}: {e}", path.display()))
+    }
+
+    /// Returns the profile called `name`
+    pub fn profile(&self, name: &str) -> Option<&Profile> {
+        self.profiles.get(name)
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    #[test]
+    fn should_parse_profiles() {
+        let config: Config = toml::from_str(
+            r#"
+            [profiles.local]
+            url = "local"
+            identity = "/home/alice/.config/dfx/identity/alice/identity.pem"
+            allow_minting = true
+
+            [profiles.testnet]
+            url = "ic"
+            dfx_identity = "testnet"
+            evmc = "4fe7g-7iaaa-aaaak-aegcq-cai"
+            chain_id = 355113
+            "#,
+        )
+        .unwrap();
+
+        let local = config.profile("local").unwrap();
+        assert_eq!(local.url.as_deref(), Some("local"));
+        assert!(local.identity.is_some());
+        assert!(local.allow_minting);
+
+        let testnet = config.profile("testnet").unwrap();
+        assert_eq!(
+            testnet.evmc,
+            Some(Principal::from_text("4fe7g-7iaaa-aaaak-aegcq-cai").unwrap())
+        );
+        assert_eq!(testnet.chain_id, Some(355113));
+        assert_eq!(testnet.dfx_identity.as_deref(), Some("testnet"));
+        assert!(!testnet.allow_minting);
+
+        assert!(config.profile("mainnet").is_none());
+    }
+}
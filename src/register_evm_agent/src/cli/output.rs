@@ -0,0 +1,434 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use candid::Principal;
+use clap::ValueEnum;
+use ethers_core::types::{Address, U256};
+use evmc_did::H160;
+use serde::Serialize;
+
+use crate::error::Error;
+use crate::registration::{RegistrationStatus, RegistrationTransaction};
+
+/// Format of the command output
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// human readable text
+    #[default]
+    Text,
+    /// a single JSON object
+    Json,
+}
+
+/// Result of a command.
+///
+/// In json output it is serialized as an object with a `result` field holding the variant name
+/// in snake case; the other fields are documented in the README and must be kept stable
+#[derive(Debug, Serialize)]
+#[serde(tag = "result", rename_all = "snake_case")]
+pub enum CommandOutput {
+    /// a wallet has been generated
+    Wallet {
+        address: String,
+        public_key: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        private_key: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        keystore: Option<PathBuf>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        mnemonic: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        derivation_path: Option<String>,
+    },
+    /// the registration succeeded
+    Registered {
+        wallet_address: String,
+        principal: Principal,
+    },
+    /// the registration transaction has been accepted, the registration is not verified yet
+    AgentRegistered {
+        wallet_address: String,
+        principal: Principal,
+    },
+    /// the wallet is already registered
+    AlreadyRegistered {
+        wallet_address: String,
+        principal: Principal,
+    },
+    /// the canister has been registered with a new wallet address
+    Rotated {
+        principal: Principal,
+        old_wallet_address: String,
+        /// whether the old address is still registered for the canister
+        old_address_registered: bool,
+        new_wallet_address: String,
+    },
+    /// the registration has been verified
+    Verified {
+        wallet_address: String,
+        principal: Principal,
+    },
+    /// a registration transaction has been signed, and possibly written to a bundle
+    RegistrationTransaction {
+        #[serde(flatten)]
+        transaction: TransactionOutput,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        bundle: Option<PathBuf>,
+    },
+    /// registration status of a wallet address for a canister
+    RegistrationStatus {
+        wallet_address: String,
+        principal: Principal,
+        registered: bool,
+        balance: String,
+        nonce: String,
+        minter_address: String,
+        registration_fee: u64,
+    },
+    /// result of every entry of a batch registration
+    Batch {
+        entries: Vec<BatchEntryOutput>,
+        failures: usize,
+    },
+    /// the command failed
+    Error {
+        #[serde(flatten)]
+        error: ErrorOutput,
+    },
+}
+
+/// Fields of a signed transaction; quantities are decimal strings
+#[derive(Debug, Serialize)]
+pub struct TransactionOutput {
+    pub hash: String,
+    pub from: String,
+    pub to: Option<String>,
+    pub value: String,
+    pub nonce: String,
+    pub chain_id: Option<String>,
+    pub gas: String,
+    pub gas_price: Option<String>,
+    pub max_fee_per_gas: Option<String>,
+    pub max_priority_fee_per_gas: Option<String>,
+    /// EIP-2718 transaction type, absent for legacy transactions
+    pub transaction_type: Option<String>,
+    pub v: String,
+    pub r: String,
+    pub s: String,
+    /// RLP encoded signed transaction
+    pub raw: String,
+}
+
+/// Result of a batch registration entry
+#[derive(Debug, Serialize)]
+pub struct BatchEntryOutput {
+    pub canister_id: Principal,
+    pub wallet_address: Option<String>,
+    #[serde(flatten)]
+    pub outcome: BatchOutcome,
+}
+
+/// Outcome of a batch registration entry
+#[derive(Debug, Serialize)]
+#[serde(tag = "result", rename_all = "snake_case")]
+pub enum BatchOutcome {
+    Registered,
+    AlreadyRegistered,
+    Failed { error: ErrorOutput },
+}
+
+/// A command error, with the name of its `Error` variant
+#[derive(Debug, Serialize)]
+pub struct ErrorOutput {
+    /// `Error` variant, or `Other` for errors which are not raised by the registration
+    pub kind: &'static str,
+    pub message: String,
+}
+
+impl From<&anyhow::Error> for ErrorOutput {
+    fn from(err: &anyhow::Error) -> Self {
+        Self {
+            kind: err
+                .downcast_ref::<Error>()
+                .map(Error::kind)
+                .unwrap_or("Other"),
+            message: err.to_string(),
+        }
+    }
+}
+
+impl CommandOutput {
+    pub fn registered(address: Address, principal: Principal) -> Self {
+        Self::Registered {
+            wallet_address: H160::from(address).to_hex_str(),
+            principal,
+        }
+    }
+
+    pub fn agent_registered(address: Address, principal: Principal) -> Self {
+        Self::AgentRegistered {
+            wallet_address: H160::from(address).to_hex_str(),
+            principal,
+        }
+    }
+
+    pub fn already_registered(address: Address, principal: Principal) -> Self {
+        Self::AlreadyRegistered {
+            wallet_address: H160::from(address).to_hex_str(),
+            principal,
+        }
+    }
+
+    pub fn rotated(
+        principal: Principal,
+        old_address: Address,
+        old_address_registered: bool,
+        new_address: Address,
+    ) -> Self {
+        Self::Rotated {
+            principal,
+            old_wallet_address: H160::from(old_address).to_hex_str(),
+            old_address_registered,
+            new_wallet_address: H160::from(new_address).to_hex_str(),
+        }
+    }
+
+    pub fn verified(address: Address, principal: Principal) -> Self {
+        Self::Verified {
+            wallet_address: H160::from(address).to_hex_str(),
+            principal,
+        }
+    }
+
+    pub fn registration_transaction(tx: &RegistrationTransaction, bundle: Option<&Path>) -> Self {
+        let transaction = &tx.transaction;
+        Self::RegistrationTransaction {
+            transaction: TransactionOutput {
+                hash: format!("{:?}", transaction.hash),
+                from: format!("{:?}", transaction.from),
+                to: transaction.to.map(|to| format!("{to:?}")),
+                value: transaction.value.to_string(),
+                nonce: transaction.nonce.to_string(),
+                chain_id: transaction.chain_id.as_ref().map(U256::to_string),
+                gas: transaction.gas.to_string(),
+                gas_price: transaction.gas_price.as_ref().map(U256::to_string),
+                max_fee_per_gas: transaction.max_fee_per_gas.as_ref().map(U256::to_string),
+                max_priority_fee_per_gas: transaction
+                    .max_priority_fee_per_gas
+                    .as_ref()
+                    .map(U256::to_string),
+                transaction_type: transaction.transaction_type.map(|t| t.to_string()),
+                v: transaction.v.to_string(),
+                r: format!("{:#x}", transaction.r),
+                s: format!("{:#x}", transaction.s),
+                raw: tx.rlp.to_string(),
+            },
+            bundle: bundle.map(Path::to_path_buf),
+        }
+    }
+
+    pub fn registration_status(status: RegistrationStatus) -> Self {
+        Self::RegistrationStatus {
+            wallet_address: status.address.to_hex_str(),
+            principal: status.register_canister_id,
+            registered: status.registered,
+            balance: status.balance.to_string(),
+            nonce: status.nonce.to_string(),
+            minter_address: status.registration_info.minter_address.to_hex_str(),
+            registration_fee: status.registration_info.registration_fee,
+        }
+    }
+
+    pub fn error(err: &anyhow::Error) -> Self {
+        Self::Error { error: err.into() }
+    }
+
+    /// Whether the command failed, entirely or for some batch entries
+    pub fn is_failure(&self) -> bool {
+        match self {
+            Self::Error { .. } => true,
+            Self::Batch { failures, .. } => *failures > 0,
+            _ => false,
+        }
+    }
+
+    /// Print the output in the given format; in text format errors are written to stderr
+    pub fn print(&self, format: OutputFormat) {
+        match (format, self) {
+            (OutputFormat::Json, _) => match serde_json::to_string_pretty(self) {
+                Ok(json) => println!("{json}"),
+                Err(e) => eprintln!("Error: failed to serialize output: {e}"),
+            },
+            (OutputFormat::Text, Self::Error { .. }) => eprintln!("{self}"),
+            (OutputFormat::Text, _) => println!("{self}"),
+        }
+    }
+}
+
+impl fmt::Display for CommandOutput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Wallet {
+                address,
+                public_key,
+                private_key,
+                keystore,
+                mnemonic,
+                derivation_path,
+            } => {
+                write!(f, "Wallet:")?;
+                if let Some(mnemonic) = mnemonic {
+                    write!(f, "\n  Mnemonic = {mnemonic}")?;
+                }
+                if let Some(derivation_path) = derivation_path {
+                    write!(f, "\n  Derivation Path = {derivation_path}")?;
+                }
+                if let Some(private_key) = private_key {
+                    write!(f, "\n  Private Key = {private_key}")?;
+                }
+                if let Some(keystore) = keystore {
+                    write!(f, "\n  Keystore = {}", keystore.display())?;
+                }
+                write!(f, "\n  Public Key = {public_key}\n  Address = {address}")
+            }
+            Self::Registered {
+                wallet_address,
+                principal,
+            } => write!(
+                f,
+                "Registration succeeded:\n  Wallet Address = {wallet_address}\n  Principal = {principal}"
+            ),
+            Self::AgentRegistered {
+                wallet_address,
+                principal,
+            } => write!(
+                f,
+                "Agent registered, the registration must be verified with the wallet signing key:\n  Wallet Address = {wallet_address}\n  Principal = {principal}"
+            ),
+            Self::AlreadyRegistered {
+                wallet_address,
+                principal,
+            } => write!(
+                f,
+                "Already registered:\n\tWallet Address = {wallet_address}\n\tPrincipal = {principal}"
+            ),
+            Self::Rotated {
+                principal,
+                old_wallet_address,
+                old_address_registered,
+                new_wallet_address,
+            } => write!(
+                f,
+                "Rotation succeeded:\n  Principal = {principal}\n  Old Wallet Address = {old_wallet_address}\n  Old Address Registered = {old_address_registered}\n  New Wallet Address = {new_wallet_address}"
+            ),
+            Self::Verified {
+                wallet_address,
+                principal,
+            } => write!(
+                f,
+                "Registration verified:\n  Wallet Address = {wallet_address}\n  Principal = {principal}"
+            ),
+            Self::RegistrationTransaction {
+                transaction: tx,
+                bundle,
+            } => {
+                write!(
+                    f,
+                    "Registration transaction:\n  Hash = {}\n  From = {}\n  To = {}\n  Value = {}\n  Nonce = {}\n  Chain Id = {}\n  Gas = {}\n  Gas Price = {}",
+                    tx.hash,
+                    tx.from,
+                    tx.to.as_deref().unwrap_or_default(),
+                    tx.value,
+                    tx.nonce,
+                    tx.chain_id.as_deref().unwrap_or_default(),
+                    tx.gas,
+                    tx.gas_price.as_deref().unwrap_or_default(),
+                )?;
+                if let Some(transaction_type) = &tx.transaction_type {
+                    write!(f, "\n  Type = {transaction_type}")?;
+                }
+                if let Some(max_fee_per_gas) = &tx.max_fee_per_gas {
+                    write!(f, "\n  Max Fee Per Gas = {max_fee_per_gas}")?;
+                }
+                if let Some(max_priority_fee_per_gas) = &tx.max_priority_fee_per_gas {
+                    write!(f, "\n  Max Priority Fee Per Gas = {max_priority_fee_per_gas}")?;
+                }
+                write!(
+                    f,
+                    "\n  V = {}\n  R = {}\n  S = {}\n  Raw = {}",
+                    tx.v, tx.r, tx.s, tx.raw,
+                )?;
+                if let Some(bundle) = bundle {
+                    write!(f, "\nRegistration bundle written to {}", bundle.display())?;
+                }
+                Ok(())
+            }
+            Self::RegistrationStatus {
+                wallet_address,
+                principal,
+                registered,
+                balance,
+                nonce,
+                minter_address,
+                registration_fee,
+            } => write!(
+                f,
+                "Registration status:\n  Wallet Address = {wallet_address}\n  Principal = {principal}\n  Registered = {registered}\n  Balance = {balance}\n  Nonce = {nonce}\n  Minter Address = {minter_address}\n  Registration Fee = {registration_fee}"
+            ),
+            Self::Batch { entries, .. } => {
+                write!(f, "{:<30} {:<42} RESULT", "CANISTER", "WALLET ADDRESS")?;
+                for entry in entries {
+                    let outcome = match &entry.outcome {
+                        BatchOutcome::Registered => "registered".to_string(),
+                        BatchOutcome::AlreadyRegistered => "already registered".to_string(),
+                        BatchOutcome::Failed { error } => format!("failed: {}", error.message),
+                    };
+                    write!(
+                        f,
+                        "\n{:<30} {:<42} {outcome}",
+                        entry.canister_id.to_text(),
+                        entry.wallet_address.as_deref().unwrap_or("-"),
+                    )?;
+                }
+                Ok(())
+            }
+            Self::Error { error } => write!(f, "Error: {}", error.message),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    #[test]
+    fn should_serialize_output_with_result_tag() {
+        let output = CommandOutput::registered(
+            Address::zero(),
+            Principal::from_text("chu2x-jyaaa-aaaah-aaqra-cai").unwrap(),
+        );
+
+        let json = serde_json::to_value(&output).unwrap();
+
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "result": "registered",
+                "wallet_address": "0x0000000000000000000000000000000000000000",
+                "principal": "chu2x-jyaaa-aaaah-aaqra-cai",
+            })
+        );
+    }
+
+    #[test]
+    fn should_serialize_error_with_kind() {
+        let err = anyhow::Error::from(Error::AlreadyRegistered(Principal::anonymous()));
+
+        let json = serde_json::to_value(CommandOutput::error(&err)).unwrap();
+
+        assert_eq!(json["result"], "error");
+        assert_eq!(json["kind"], "AlreadyRegistered");
+        assert_eq!(json["message"], err.to_string());
+    }
+}
@@ -0,0 +1,131 @@
+use anyhow::Result;
+use candid::Principal;
+use clap::Args;
+use eth_signer::Signer;
+use ethers_core::types::Address;
+use evmc_did::H160;
+
+use super::{ensure_controller, CommandOutput, ConnectionArgs, GasArgs, WalletArgs};
+use crate::error::Error;
+use crate::evmc::AgentEvmcClient;
+use crate::registration::RegistrationService;
+
+#[derive(Args)]
+pub struct RotateArgs {
+    /// maximum amount of native tokens to mint on testnets for the new wallet, if its balance
+    /// doesn't cover the registration cost
+    #[arg(short = 'a', long = "amount-to-mint")]
+    pub amount_to_mint: Option<u64>,
+
+    /// chain id [default: the EVMC chain id]
+    #[arg(short = 'C', long = "chain-id")]
+    pub chain_id: Option<u64>,
+
+    #[command(flatten)]
+    pub connection: ConnectionArgs,
+
+    /// Principal of the registered canister
+    #[arg(short = 'c', long = "canister-id")]
+    pub register_canister_id: Principal,
+
+    /// Address currently registered for the canister
+    #[arg(long = "old-address")]
+    pub old_address: Address,
+
+    /// The new wallet
+    #[command(flatten)]
+    pub wallet: WalletArgs,
+
+    #[command(flatten)]
+    pub gas: GasArgs,
+
+    /// Proceed, with a warning, even if the identity is not a controller of the canister
+    #[arg(long = "force")]
+    pub force: bool,
+}
+
+impl RotateArgs {
+    pub async fn exec(&self) -> Result<CommandOutput> {
+        let wallet = self.wallet.wallet()?;
+        let new_address = wallet.address();
+        if new_address == self.old_address {
+            anyhow::bail!("the new wallet address is the registered one");
+        }
+
+        let connection = self.connection.resolve()?;
+        let evmc = connection.evmc()?;
+        let amount_to_mint = connection.amount_to_mint(self.amount_to_mint)?;
+        let agent = connection.agent().await?;
+        ensure_controller(&agent, self.register_canister_id, self.force).await?;
+        let client = AgentEvmcClient::new(agent.clone(), evmc);
+
+        let old_status = RegistrationService::status(
+            &client,
+            &self.register_canister_id,
+            &H160::from(self.old_address),
+        )
+        .await?;
+        if !old_status.registered {
+            anyhow::bail!(
+                "{} is not registered for {}",
+                old_status.address.to_hex_str(),
+                self.register_canister_id
+            );
+        }
+
+        let new_status = RegistrationService::status(
+            &client,
+            &self.register_canister_id,
+            &H160::from(new_address),
+        )
+        .await?;
+        if new_status.registered {
+            return Err(Error::AlreadyRegistered(self.register_canister_id).into());
+        }
+
+        // the EVMC interface has no method to unregister an address, so the old binding
+        // can't be removed before registering the new one
+        warn!(
+            "the EVMC doesn't support unregistering, {} stays registered",
+            old_status.address.to_hex_str()
+        );
+
+        let mut builder = RegistrationService::builder()
+            .evmc(AgentEvmcClient::new(agent, evmc))
+            .target_canister(self.register_canister_id)
+            .wallet(wallet)
+            .amount_to_mint(amount_to_mint)
+            .gas_strategy(self.gas.gas_strategy())
+            .retry_policy(connection.retry_policy())
+            .registration_info(new_status.registration_info.clone());
+        if let Some(chain_id) = connection.chain_id(self.chain_id) {
+            builder = builder.chain_id(chain_id);
+        }
+        let registration_service = builder.build().await?;
+
+        registration_service.register().await?;
+
+        let new_registered =
+            RegistrationService::status(&client, &self.register_canister_id, &new_status.address)
+                .await?
+                .registered;
+        if !new_registered {
+            anyhow::bail!(
+                "{} is not registered for {} after the rotation",
+                new_status.address.to_hex_str(),
+                self.register_canister_id
+            );
+        }
+        let old_registered =
+            RegistrationService::status(&client, &self.register_canister_id, &old_status.address)
+                .await?
+                .registered;
+
+        Ok(CommandOutput::rotated(
+            self.register_canister_id,
+            self.old_address,
+            old_registered,
+            new_address,
+        ))
+    }
+}
@@ -0,0 +1,39 @@
+use anyhow::Result;
+use candid::Principal;
+use clap::Args;
+use ethers_core::types::Address;
+use evmc_did::H160;
+
+use super::{CommandOutput, ConnectionArgs};
+use crate::evmc::AgentEvmcClient;
+use crate::registration::RegistrationService;
+
+#[derive(Args)]
+pub struct StatusArgs {
+    #[command(flatten)]
+    pub connection: ConnectionArgs,
+
+    /// Principal of the registered canister
+    #[arg(short = 'c', long = "canister-id")]
+    pub register_canister_id: Principal,
+
+    /// Wallet address
+    #[arg(short = 'w', long = "address")]
+    pub address: Address,
+}
+
+impl StatusArgs {
+    pub async fn exec(&self) -> Result<CommandOutput> {
+        let connection = self.connection.resolve()?;
+        let client = AgentEvmcClient::new(connection.agent().await?, connection.evmc()?);
+
+        let status = RegistrationService::status(
+            &client,
+            &self.register_canister_id,
+            &H160::from(self.address),
+        )
+        .await?;
+
+        Ok(CommandOutput::registration_status(status))
+    }
+}
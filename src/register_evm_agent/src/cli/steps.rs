@@ -0,0 +1,42 @@
+use ethers_core::types::{Address, TxHash, U256};
+use evmc_did::registration_info::RegistrationInfo;
+
+use crate::error::Error;
+use crate::observer::{RegistrationObserver, RegistrationStage};
+
+/// Observer printing the registration steps to stderr, leaving stdout to the command output
+pub struct StepPrinter;
+
+impl RegistrationObserver for StepPrinter {
+    fn info_fetched(&self, registration_info: &RegistrationInfo) {
+        eprintln!(
+            "registration info fetched: fee {}, minter {}",
+            registration_info.registration_fee,
+            registration_info.minter_address.to_hex_str()
+        );
+    }
+
+    fn nonce_fetched(&self, nonce: U256) {
+        eprintln!("nonce fetched: {nonce}");
+    }
+
+    fn transaction_signed(&self, hash: TxHash) {
+        eprintln!("registration transaction signed: {hash:?}");
+    }
+
+    fn tokens_minted(&self, address: Address, amount: U256) {
+        eprintln!("minted {amount} native tokens to {address:?}");
+    }
+
+    fn agent_registered(&self) {
+        eprintln!("agent registered");
+    }
+
+    fn registration_verified(&self) {
+        eprintln!("registration verified");
+    }
+
+    fn step_failed(&self, stage: RegistrationStage, error: &Error) {
+        eprintln!("{stage:?} failed: {error}");
+    }
+}
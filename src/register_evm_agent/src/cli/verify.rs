@@ -0,0 +1,54 @@
+use anyhow::Result;
+use candid::Principal;
+use clap::Args;
+use eth_signer::Signer;
+
+use super::{progress_dir, CommandOutput, ConnectionArgs, WalletArgs};
+use crate::evmc::AgentEvmcClient;
+use crate::progress::{RegistrationProgress, RegistrationStep};
+use crate::registration::RegistrationService;
+
+#[derive(Args)]
+pub struct VerifyArgs {
+    #[command(flatten)]
+    pub connection: ConnectionArgs,
+
+    /// chain id [default: the EVMC chain id]
+    #[arg(short = 'C', long = "chain-id")]
+    pub chain_id: Option<u64>,
+
+    /// Principal of the registered canister
+    #[arg(short = 'c', long = "canister-id")]
+    pub register_canister_id: Principal,
+
+    #[command(flatten)]
+    pub wallet: WalletArgs,
+}
+
+impl VerifyArgs {
+    pub async fn exec(&self) -> Result<CommandOutput> {
+        let wallet = self.wallet.wallet()?;
+        let address = wallet.address();
+
+        let connection = self.connection.resolve()?;
+        let evmc = connection.evmc()?;
+        let agent = connection.agent().await?;
+
+        let mut builder = RegistrationService::builder()
+            .evmc(AgentEvmcClient::new(agent, evmc))
+            .target_canister(self.register_canister_id)
+            .wallet(wallet)
+            .retry_policy(connection.retry_policy());
+        if let Some(chain_id) = connection.chain_id(self.chain_id) {
+            builder = builder.chain_id(chain_id);
+        }
+
+        builder.build().await?.verify_registration().await?;
+
+        let mut progress =
+            RegistrationProgress::load(&progress_dir()?, evmc, self.register_canister_id, address)?;
+        progress.set_step(RegistrationStep::Verified)?;
+
+        Ok(CommandOutput::verified(address, self.register_canister_id))
+    }
+}
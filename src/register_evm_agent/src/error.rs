@@ -1,39 +1,118 @@
 use std::path::PathBuf;
+use std::time::Duration;
 
 use candid::Principal;
+use eth_keystore::KeystoreError;
 use eth_signer::WalletError;
+use ethers_core::types::U256;
 use evmc_did::error::EvmError;
 use ic_agent::identity::PemError;
 use ic_agent::AgentError;
 use rlp::DecoderError;
 use thiserror::Error;
 
+use crate::agent::KeyType;
+
 pub type Result<T> = std::result::Result<T, Error>;
 
 #[derive(Error, Debug)]
 pub enum Error {
     #[error("IPC agent error: {0}")]
     Agent(AgentError),
-    #[error("wallet is already registered: {0}")]
+    #[error("wallet is already registered for canister {0}")]
     AlreadyRegistered(Principal),
+    #[error("invalid registration bundle {0}: {1}")]
+    Bundle(PathBuf, String),
+    #[error("chain id {0} doesn't match the EVMC chain id {1}")]
+    ChainIdMismatch(u64, u64),
     #[error("Failed to check registration status:\n  Wallet Address = {0}\n  Principal = {1}")]
     CouldNotCheckRegistrationStatus(String, Principal),
+    #[error("failed to estimate gas: {0}; the gas limit can be set explicitly")]
+    CouldNotEstimateGas(String),
+    #[error("failed to get the controllers of canister {0}: {1}")]
+    CouldNotGetControllers(Principal, String),
+    #[error("failed to get gas price: {0}; the gas price can be set explicitly")]
+    CouldNotGetGasPrice(String),
     #[error("failed to get agent principal: {0}")]
     CouldNotGetPrincipal(String),
     #[error("failed to get registration info: {0}")]
     CouldNotGetRegistrationInfo(String),
     #[error("could not decode transaction: {0}")]
     Decoder(DecoderError),
+    #[error("failed to load dfx identity {0}: {1}")]
+    DfxIdentity(String, String),
     #[error("EVM error: {0}")]
     Evm(EvmError),
+    #[error("failed to load {1} identity {0}: {2}")]
+    Identity(PathBuf, KeyType, String),
+    #[error(
+        "insufficient funds: the registration needs {needed}, the wallet balance is {available}"
+    )]
+    InsufficientFunds { needed: U256, available: U256 },
+    #[error("invalid registration service: {0}")]
+    InvalidRegistrationService(String),
+    #[error("the registration transaction doesn't match the EVMC: {0}")]
+    InvalidRegistrationTransaction(String),
+    #[error("failed to access keystore {0}: {1}")]
+    Keystore(PathBuf, KeystoreError),
+    #[error("invalid mnemonic: {0}")]
+    Mnemonic(String),
+    #[error("{0} is not a controller of canister {1}")]
+    NotController(Principal, Principal),
     #[error("parse error: {0}")]
     Parse(candid::Error),
     #[error("failed to read PEM file {0}: {1}")]
     Pem(PathBuf, PemError),
+    #[error("failed to access registration progress {0}: {1}")]
+    Progress(PathBuf, String),
+    #[error("invalid root key {0}: {1}")]
+    RootKey(PathBuf, String),
+    #[error("failed to sign transaction: {0}")]
+    Signing(String),
+    #[error("{0} timed out after {1:?}")]
+    Timeout(String, Duration),
+    #[error("invalid transaction signature: {0}")]
+    TransactionSignature(String),
     #[error("wallet error: {0}")]
     Wallet(WalletError),
 }
 
+impl Error {
+    /// Returns the name of the error variant
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Agent(_) => "Agent",
+            Self::AlreadyRegistered(_) => "AlreadyRegistered",
+            Self::Bundle(_, _) => "Bundle",
+            Self::ChainIdMismatch(_, _) => "ChainIdMismatch",
+            Self::CouldNotCheckRegistrationStatus(_, _) => "CouldNotCheckRegistrationStatus",
+            Self::CouldNotEstimateGas(_) => "CouldNotEstimateGas",
+            Self::CouldNotGetControllers(_, _) => "CouldNotGetControllers",
+            Self::CouldNotGetGasPrice(_) => "CouldNotGetGasPrice",
+            Self::CouldNotGetPrincipal(_) => "CouldNotGetPrincipal",
+            Self::CouldNotGetRegistrationInfo(_) => "CouldNotGetRegistrationInfo",
+            Self::Decoder(_) => "Decoder",
+            Self::DfxIdentity(_, _) => "DfxIdentity",
+            Self::Evm(_) => "Evm",
+            Self::Identity(_, _, _) => "Identity",
+            Self::InsufficientFunds { .. } => "InsufficientFunds",
+            Self::InvalidRegistrationService(_) => "InvalidRegistrationService",
+            Self::InvalidRegistrationTransaction(_) => "InvalidRegistrationTransaction",
+            Self::Keystore(_, _) => "Keystore",
+            Self::Mnemonic(_) => "Mnemonic",
+            Self::NotController(_, _) => "NotController",
+            Self::Parse(_) => "Parse",
+            Self::Pem(_, _) => "Pem",
+            Self::Progress(_, _) => "Progress",
+            Self::RootKey(_, _) => "RootKey",
+            Self::Signing(_) => "Signing",
+            Self::Timeout(_, _) => "Timeout",
+            Self::TransactionSignature(_) => "TransactionSignature",
+            Self::Wallet(_) => "Wallet",
+        }
+    }
+}
+
 impl From<AgentError> for Error {
     fn from(err: AgentError) -> Self {
         Self::Agent(err)
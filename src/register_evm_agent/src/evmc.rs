@@ -0,0 +1,216 @@
+use async_trait::async_trait;
+use candid::utils::ArgumentEncoder;
+use candid::{CandidType, Deserialize, Principal};
+use ethers_core::types::Address;
+use evmc_did::registration_info::RegistrationInfo;
+use evmc_did::{BasicAccount, Transaction, H160, U256};
+use ic_agent::Agent;
+#[cfg(test)]
+use mockall::automock;
+
+use self::methods::{
+    CallMode, EvmcMethod, ACCOUNT_BASIC, ETH_CHAIN_ID, ETH_ESTIMATE_GAS, GET_MIN_GAS_PRICE,
+    IS_ADDRESS_REGISTERED, MINT_EVM_TOKENS, REGISTER_IC_AGENT, REGISTRATION_IC_AGENT_INFO,
+    VERIFY_REGISTRATION,
+};
+use crate::agent::user_principal;
+use crate::error::{Error, Result};
+use crate::gas::parse_quantity;
+
+pub mod methods;
+
+/// upper bound of the gas estimated for the registration transaction
+const ESTIMATE_GAS_LIMIT: u64 = 30_000_000;
+
+/// Interface for calling the EVMC methods used by the registration
+#[cfg_attr(test, automock)]
+#[async_trait]
+pub trait EvmcClient: Send + Sync {
+    /// Principal of the caller
+    fn principal(&self) -> Result<Principal>;
+
+    /// Returns the chain id of the EVMC
+    async fn chain_id(&self) -> Result<u64>;
+
+    /// Returns the minter address and the registration fee
+    async fn registration_info(&self) -> Result<RegistrationInfo>;
+
+    /// Returns the balance and the nonce of `address`
+    async fn account_basic(&self, address: H160) -> Result<BasicAccount>;
+
+    /// Whether `address` is registered for `principal`
+    async fn is_address_registered(&self, address: H160, principal: Principal) -> Result<bool>;
+
+    /// Registers `principal` with the signed registration transaction
+    async fn register_ic_agent(&self, transaction: Transaction, principal: Principal)
+        -> Result<()>;
+
+    /// Verifies the registration of `principal` with the wallet signing key
+    async fn verify_registration(&self, signing_key: Vec<u8>, principal: Principal) -> Result<()>;
+
+    /// Mints `amount` native tokens to `address`, returning the new balance
+    async fn mint_native_tokens(&self, address: H160, amount: U256) -> Result<U256>;
+
+    /// Returns the minimum gas price accepted by the EVMC
+    async fn min_gas_price(&self) -> Result<ethers_core::types::U256>;
+
+    /// Returns the gas estimated for a transfer of `value` from `from` to `to`
+    async fn estimate_gas(
+        &self,
+        from: Address,
+        to: Address,
+        value: ethers_core::types::U256,
+    ) -> Result<ethers_core::types::U256>;
+}
+
+/// `EvmcClient` calling the EVMC canister through an IC agent
+pub struct AgentEvmcClient {
+    agent: Agent,
+    evmc_canister_id: Principal,
+}
+
+impl AgentEvmcClient {
+    pub fn new(agent: Agent, evmc_canister_id: Principal) -> Self {
+        Self {
+            agent,
+            evmc_canister_id,
+        }
+    }
+
+    /// Calls `method` as a query or as an update, according to its mode,
+    /// returning the undecoded result
+    async fn call<A, R>(&self, method: &EvmcMethod<A, R>, args: A) -> Result<Vec<u8>>
+    where
+        A: ArgumentEncoder,
+        R: CandidType + for<'de> Deserialize<'de>,
+    {
+        let args = method.encode(args)?;
+        let res = match method.mode {
+            CallMode::Query => {
+                self.agent
+                    .query(&self.evmc_canister_id, method.name)
+                    .with_arg(args)
+                    .call()
+                    .await?
+            }
+            CallMode::Update => {
+                self.agent
+                    .update(&self.evmc_canister_id, method.name)
+                    .with_arg(args)
+                    .call_and_wait()
+                    .await?
+            }
+        };
+
+        Ok(res)
+    }
+}
+
+#[async_trait]
+impl EvmcClient for AgentEvmcClient {
+    fn principal(&self) -> Result<Principal> {
+        user_principal(&self.agent)
+    }
+
+    async fn chain_id(&self) -> Result<u64> {
+        let res = self.call(&ETH_CHAIN_ID, ()).await?;
+
+        Ok(ETH_CHAIN_ID.decode(&res)?)
+    }
+
+    async fn registration_info(&self) -> Result<RegistrationInfo> {
+        let res = self.call(&REGISTRATION_IC_AGENT_INFO, ()).await?;
+
+        REGISTRATION_IC_AGENT_INFO
+            .decode(&res)
+            .map_err(|e| Error::CouldNotGetRegistrationInfo(e.to_string()))
+    }
+
+    async fn account_basic(&self, address: H160) -> Result<BasicAccount> {
+        let res = self.call(&ACCOUNT_BASIC, (address,)).await?;
+
+        Ok(ACCOUNT_BASIC.decode(&res)?)
+    }
+
+    async fn is_address_registered(&self, address: H160, principal: Principal) -> Result<bool> {
+        info!("checking if {address} is already registered...");
+        let res = self
+            .call(&IS_ADDRESS_REGISTERED, (address.clone(), principal))
+            .await?;
+        match IS_ADDRESS_REGISTERED.decode(&res) {
+            Ok(res) => {
+                info!("{address} registered: {res}");
+                Ok(res)
+            }
+            Err(_) => Err(Error::CouldNotCheckRegistrationStatus(
+                address.to_hex_str(),
+                principal,
+            )),
+        }
+    }
+
+    async fn register_ic_agent(
+        &self,
+        transaction: Transaction,
+        principal: Principal,
+    ) -> Result<()> {
+        let res = self
+            .call(&REGISTER_IC_AGENT, (transaction, principal))
+            .await?;
+
+        info!("{} called, decoding result", REGISTER_IC_AGENT.name);
+        REGISTER_IC_AGENT.decode(&res)??;
+        info!("result is OK");
+
+        Ok(())
+    }
+
+    async fn verify_registration(&self, signing_key: Vec<u8>, principal: Principal) -> Result<()> {
+        let res = self
+            .call(&VERIFY_REGISTRATION, (signing_key, principal))
+            .await?;
+
+        info!("{} called, decoding result", VERIFY_REGISTRATION.name);
+        VERIFY_REGISTRATION.decode(&res)??;
+        info!("result is OK");
+
+        Ok(())
+    }
+
+    async fn mint_native_tokens(&self, address: H160, amount: U256) -> Result<U256> {
+        let res = self.call(&MINT_EVM_TOKENS, (address, amount)).await?;
+
+        Ok(MINT_EVM_TOKENS.decode(&res)??)
+    }
+
+    async fn min_gas_price(&self) -> Result<ethers_core::types::U256> {
+        info!("querying gas price...");
+        let res = self.call(&GET_MIN_GAS_PRICE, ()).await?;
+        let gas_price = GET_MIN_GAS_PRICE.decode(&res)?;
+
+        parse_quantity(&gas_price).map_err(Error::CouldNotGetGasPrice)
+    }
+
+    async fn estimate_gas(
+        &self,
+        from: Address,
+        to: Address,
+        value: ethers_core::types::U256,
+    ) -> Result<ethers_core::types::U256> {
+        info!("estimating gas...");
+        let args = (
+            H160::from(from).to_hex_str(),
+            Some(H160::from(to).to_hex_str()),
+            ESTIMATE_GAS_LIMIT,
+            format!("{value:#x}"),
+            String::from("0x"),
+        );
+
+        let res = self.call(&ETH_ESTIMATE_GAS, args).await?;
+        let gas = ETH_ESTIMATE_GAS
+            .decode(&res)?
+            .map_err(|e| Error::CouldNotEstimateGas(e.to_string()))?;
+
+        parse_quantity(&gas).map_err(Error::CouldNotEstimateGas)
+    }
+}
@@ -0,0 +1,165 @@
+use std::marker::PhantomData;
+
+use candid::utils::ArgumentEncoder;
+use candid::{CandidType, Deserialize, Principal};
+use evmc_did::error::EvmError;
+use evmc_did::registration_info::RegistrationInfo;
+use evmc_did::{BasicAccount, Transaction, H160, U256};
+
+type EvmResult<T> = std::result::Result<T, EvmError>;
+
+/// Whether a method is called as a query or as an update
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CallMode {
+    Query,
+    Update,
+}
+
+/// EVMC method taking the arguments `A`, as a tuple, and returning `R`
+pub struct EvmcMethod<A, R> {
+    pub name: &'static str,
+    pub mode: CallMode,
+    types: PhantomData<fn(A) -> R>,
+}
+
+impl<A, R> EvmcMethod<A, R> {
+    const fn new(name: &'static str, mode: CallMode) -> Self {
+        Self {
+            name,
+            mode,
+            types: PhantomData,
+        }
+    }
+}
+
+impl<A, R> EvmcMethod<A, R>
+where
+    A: ArgumentEncoder,
+    R: CandidType + for<'de> Deserialize<'de>,
+{
+    /// Encodes the arguments of a call
+    pub fn encode(&self, args: A) -> candid::Result<Vec<u8>> {
+        candid::encode_args(args)
+    }
+
+    /// Decodes the result of a call
+    pub fn decode(&self, bytes: &[u8]) -> candid::Result<R> {
+        candid::decode_one(bytes)
+    }
+}
+
+/// Declares the EVMC methods, together with the Candid interface they expect from the EVMC,
+/// which is checked with Candid subtyping against the `evmc.did` of the simple canister
+macro_rules! evmc_methods {
+    ($($(#[$doc:meta])* $name:ident: $mode:ident $method:literal ($($arg:ty),*) -> $ret:ty;)*) => {
+        $(
+            $(#[$doc])*
+            pub const $name: EvmcMethod<($($arg,)*), $ret> =
+                EvmcMethod::new($method, CallMode::$mode);
+        )*
+
+        /// Candid service made of the declared methods
+        #[cfg(test)]
+        fn expected_interface() -> String {
+            use candid::parser::types::FuncMode;
+            use candid::types::internal::TypeContainer;
+            use candid::types::{Function, Type};
+
+            let mut env = TypeContainer::new();
+            let mut service = vec![$(
+                (
+                    $method.to_string(),
+                    Type::Func(Function {
+                        modes: match CallMode::$mode {
+                            CallMode::Query => vec![FuncMode::Query],
+                            CallMode::Update => vec![],
+                        },
+                        args: vec![$(env.add::<$arg>()),*],
+                        rets: vec![env.add::<$ret>()],
+                    }),
+                ),
+            )*];
+            service.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+
+            candid::bindings::candid::compile(&env.env, &Some(Type::Service(service)))
+        }
+    };
+}
+
+evmc_methods! {
+    /// chain id of the EVMC
+    ETH_CHAIN_ID: Query "eth_chain_id" () -> u64;
+    /// minter address and registration fee
+    REGISTRATION_IC_AGENT_INFO: Query "registration_ic_agent_info" () -> RegistrationInfo;
+    /// balance and nonce of an address
+    ACCOUNT_BASIC: Query "account_basic" (H160) -> BasicAccount;
+    /// whether an address is registered for a principal
+    IS_ADDRESS_REGISTERED: Query "is_address_registered" (H160, Principal) -> bool;
+    /// registers a principal with a signed registration transaction
+    REGISTER_IC_AGENT: Update "register_ic_agent" (Transaction, Principal) -> EvmResult<()>;
+    /// verifies the registration of a principal with the wallet signing key
+    VERIFY_REGISTRATION: Update "verify_registration" (Vec<u8>, Principal) -> EvmResult<()>;
+    /// mints native tokens to an address, returning its new balance
+    MINT_EVM_TOKENS: Update "mint_evm_tokens" (H160, U256) -> EvmResult<U256>;
+    /// minimum gas price, as a hex quantity
+    GET_MIN_GAS_PRICE: Query "get_min_gas_price" () -> String;
+    /// gas of a transaction (from, to, gas limit, value, data), as a hex quantity
+    ETH_ESTIMATE_GAS: Query "eth_estimate_gas" (String, Option<String>, u64, String, String)
+        -> EvmResult<String>;
+}
+
+#[cfg(test)]
+mod test {
+
+    use candid::utils::{service_compatible, CandidSource};
+    use ethers_core::types::Address;
+
+    use super::*;
+
+    #[test]
+    fn should_match_the_evmc_interface() {
+        let expected = expected_interface();
+        let did = include_str!("../../../simple_canister/evmc.did");
+
+        // the EVMC must be usable where the declared methods are expected:
+        // it may take more general arguments and return more specific results
+        service_compatible(CandidSource::Text(did), CandidSource::Text(&expected)).unwrap_or_else(
+            |e| panic!("the EVMC interface doesn't match the declared methods: {e}\n{expected}"),
+        );
+    }
+
+    #[test]
+    fn should_detect_a_mismatching_method() {
+        let did = r#"service : {
+            is_address_registered : (text, principal) -> (text) query;
+        }"#;
+        let expected = r#"service : {
+            is_address_registered : (text, principal) -> (bool) query;
+        }"#;
+
+        assert!(service_compatible(CandidSource::Text(did), CandidSource::Text(expected)).is_err());
+    }
+
+    #[test]
+    fn should_encode_and_decode_calls() {
+        let address = H160::from(
+            "0xb0e5863d0ddf7e105e409fee0ecc0123a362e14b"
+                .parse::<Address>()
+                .unwrap(),
+        );
+        let args = IS_ADDRESS_REGISTERED
+            .encode((address.clone(), Principal::anonymous()))
+            .unwrap();
+        assert_eq!(
+            candid::decode_args::<(H160, Principal)>(&args).unwrap(),
+            (address, Principal::anonymous())
+        );
+
+        let res = candid::encode_one(EvmResult::<()>::Err(EvmError::NotAuthorized)).unwrap();
+        assert!(matches!(
+            REGISTER_IC_AGENT.decode(&res).unwrap(),
+            Err(EvmError::NotAuthorized)
+        ));
+        assert!(IS_ADDRESS_REGISTERED.decode(&res).is_err());
+    }
+}
@@ -0,0 +1,104 @@
+use ethers_core::types::U256;
+
+/// gas limit used when the transaction is signed offline and no gas limit is given
+pub const DEFAULT_GAS: u64 = 53000;
+
+/// Fees of the registration transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransactionFees {
+    /// legacy transaction, with a fixed gas price
+    Legacy { gas_price: U256 },
+    /// EIP-1559 (type 2) transaction
+    Eip1559 {
+        max_fee_per_gas: U256,
+        max_priority_fee_per_gas: U256,
+    },
+}
+
+impl Default for TransactionFees {
+    fn default() -> Self {
+        Self::Legacy {
+            gas_price: 0.into(),
+        }
+    }
+}
+
+/// Gas limit and fees of the registration transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransactionGas {
+    pub gas: U256,
+    pub fees: TransactionFees,
+}
+
+impl Default for TransactionGas {
+    fn default() -> Self {
+        Self {
+            gas: DEFAULT_GAS.into(),
+            fees: TransactionFees::default(),
+        }
+    }
+}
+
+/// How the gas of the registration transaction is chosen: the values which are not set
+/// are queried from the EVMC
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GasStrategy {
+    /// gas limit; estimated by the EVMC if not set
+    pub gas: Option<U256>,
+    /// fees; if not set, a legacy transaction is built with the EVMC minimum gas price
+    pub fees: Option<TransactionFees>,
+}
+
+impl GasStrategy {
+    /// Returns the gas of a transaction signed without access to the EVMC,
+    /// using the defaults for the values which are not set
+    pub fn offline(&self) -> TransactionGas {
+        let default = TransactionGas::default();
+        TransactionGas {
+            gas: self.gas.unwrap_or(default.gas),
+            fees: self.fees.unwrap_or(default.fees),
+        }
+    }
+}
+
+/// Parses a quantity returned by the EVMC, either `0x` prefixed hex or decimal
+pub fn parse_quantity(quantity: &str) -> Result<U256, String> {
+    match quantity.strip_prefix("0x") {
+        Some(hex) => U256::from_str_radix(hex, 16).map_err(|e| e.to_string()),
+        None => U256::from_dec_str(quantity).map_err(|e| e.to_string()),
+    }
+    .map_err(|e| format!("invalid quantity {quantity}: {e}"))
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    #[test]
+    fn should_parse_quantities() {
+        assert_eq!(parse_quantity("0x5208").unwrap(), U256::from(21000));
+        assert_eq!(parse_quantity("21000").unwrap(), U256::from(21000));
+        assert!(parse_quantity("0xzz").is_err());
+    }
+
+    #[test]
+    fn should_use_defaults_offline() {
+        let strategy = GasStrategy {
+            gas: None,
+            fees: Some(TransactionFees::Legacy {
+                gas_price: 10.into(),
+            }),
+        };
+
+        assert_eq!(
+            strategy.offline(),
+            TransactionGas {
+                gas: DEFAULT_GAS.into(),
+                fees: TransactionFees::Legacy {
+                    gas_price: 10.into()
+                },
+            }
+        );
+    }
+}
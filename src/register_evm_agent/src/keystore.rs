@@ -0,0 +1,82 @@
+use std::path::Path;
+
+use eth_keystore::KeystoreError;
+use eth_signer::Wallet;
+use ethers_core::k256::ecdsa::SigningKey;
+
+use crate::error::{Error, Result};
+
+/// Encrypt the wallet signing key with `password` and write it to `path`
+/// as a Web3 Secret Storage (keystore v3) JSON file
+pub fn encrypt_wallet(path: &Path, wallet: &Wallet<'_, SigningKey>, password: &str) -> Result<()> {
+    let dir = path
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."));
+    let name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| {
+            Error::Keystore(
+                path.to_path_buf(),
+                KeystoreError::StdIo("invalid keystore file name".to_string()),
+            )
+        })?;
+
+    let mut rng = rand::thread_rng();
+    eth_keystore::encrypt_key(
+        dir,
+        &mut rng,
+        wallet.signer().to_bytes(),
+        password,
+        Some(name),
+    )
+    .map_err(|e| Error::Keystore(path.to_path_buf(), e))?;
+
+    Ok(())
+}
+
+/// Read the Web3 Secret Storage (keystore v3) JSON file at `path`
+/// and decrypt the wallet with `password`
+pub fn decrypt_wallet<'a>(path: &Path, password: &str) -> Result<Wallet<'a, SigningKey>> {
+    let key_bytes = eth_keystore::decrypt_key(path, password)
+        .map_err(|e| Error::Keystore(path.to_path_buf(), e))?;
+    let wallet = Wallet::from_bytes(&key_bytes)?;
+
+    Ok(wallet)
+}
+
+#[cfg(test)]
+mod test {
+
+    use eth_signer::Signer;
+
+    use super::*;
+
+    #[test]
+    fn should_encrypt_and_decrypt_wallet() {
+        let path = std::env::temp_dir().join("register_evm_agent_keystore_roundtrip.json");
+        let wallet = Wallet::new(&mut rand::thread_rng());
+
+        encrypt_wallet(&path, &wallet, "password").unwrap();
+        let decrypted = decrypt_wallet(&path, "password").unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(wallet.address(), decrypted.address());
+    }
+
+    #[test]
+    fn should_not_decrypt_wallet_with_wrong_password() {
+        let path = std::env::temp_dir().join("register_evm_agent_keystore_wrong_password.json");
+        let wallet = Wallet::new(&mut rand::thread_rng());
+
+        encrypt_wallet(&path, &wallet, "password").unwrap();
+        let result = decrypt_wallet(&path, "not the password");
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(matches!(
+            result,
+            Err(Error::Keystore(_, KeystoreError::MacMismatch))
+        ));
+    }
+}
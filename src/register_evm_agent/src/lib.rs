@@ -2,9 +2,19 @@
 extern crate log;
 
 pub mod agent;
-mod constant;
+pub mod bundle;
 mod error;
+pub mod evmc;
+pub mod gas;
+pub mod keystore;
+pub mod mnemonic;
+pub mod observer;
+pub mod progress;
 mod registration;
+pub mod retry;
 
 pub use error::{Error, Result};
-pub use registration::RegistrationService;
+pub use registration::{
+    sign_registration_transaction, RegistrationService, RegistrationServiceBuilder,
+    RegistrationStatus, RegistrationTransaction, TransactionSigner,
+};
@@ -1,27 +1,36 @@
-use anyhow::{Ok, Result};
 use clap::Parser;
-use cli::{generate_wallet, Commands, RegisterMinterCli};
+use cli::{CommandOutput, RegisterMinterCli};
 
 #[macro_use]
 extern crate log;
 
 mod agent;
+mod bundle;
 mod cli;
-mod constant;
 mod error;
+mod evmc;
+mod gas;
+mod keystore;
+mod mnemonic;
+mod observer;
+mod progress;
 mod registration;
+mod retry;
 
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() {
     env_logger::init();
 
     let cli = RegisterMinterCli::parse();
 
-    match cli.command {
-        Commands::GenerateWallet => {
-            generate_wallet()?;
-            Ok(())
-        }
-        Commands::Register(register_args) => register_args.exec().await,
+    let output = cli
+        .command
+        .exec()
+        .await
+        .unwrap_or_else(|e| CommandOutput::error(&e));
+    output.print(cli.output);
+
+    if output.is_failure() {
+        std::process::exit(1);
     }
 }
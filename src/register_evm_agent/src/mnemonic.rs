@@ -0,0 +1,80 @@
+use coins_bip39::{English, Mnemonic};
+use eth_signer::Wallet;
+use ethers_core::k256::ecdsa::SigningKey;
+
+use crate::error::{Error, Result};
+
+/// Supported mnemonic lengths
+const WORD_COUNTS: [usize; 2] = [12, 24];
+
+/// Returns the BIP-44 derivation path of the Ethereum account at `index`
+pub fn derivation_path(index: u32) -> String {
+    format!("m/44'/60'/0'/0/{index}")
+}
+
+/// Generate a random BIP-39 english mnemonic phrase of 12 or 24 words
+pub fn generate_mnemonic(word_count: usize) -> Result<String> {
+    if !WORD_COUNTS.contains(&word_count) {
+        return Err(Error::Mnemonic(format!(
+            "invalid word count {word_count}, expected 12 or 24"
+        )));
+    }
+
+    let mut rng = rand::thread_rng();
+    let mnemonic = Mnemonic::<English>::new_with_count(&mut rng, word_count)
+        .map_err(|e| Error::Mnemonic(e.to_string()))?;
+    Ok(mnemonic.to_phrase())
+}
+
+/// Derive the wallet at `m/44'/60'/0'/0/<index>` from a BIP-39 english mnemonic phrase
+pub fn derive_wallet<'a>(phrase: &str, index: u32) -> Result<Wallet<'a, SigningKey>> {
+    let mnemonic = Mnemonic::<English>::new_from_phrase(phrase.trim())
+        .map_err(|e| Error::Mnemonic(e.to_string()))?;
+    let key = mnemonic
+        .derive_key(derivation_path(index).as_str(), None)
+        .map_err(|e| Error::Mnemonic(e.to_string()))?;
+    let key: &coins_bip32::prelude::SigningKey = key.as_ref();
+
+    Ok(Wallet::from_bytes(key.to_bytes().as_slice())?)
+}
+
+#[cfg(test)]
+mod test {
+
+    use eth_signer::Signer;
+    use ethers_core::types::Address;
+
+    use super::*;
+
+    const PHRASE: &str = "test test test test test test test test test test test junk";
+
+    #[test]
+    fn should_derive_wallets_from_mnemonic() {
+        let first = derive_wallet(PHRASE, 0).unwrap();
+        let second = derive_wallet(PHRASE, 1).unwrap();
+
+        assert_eq!(
+            first.address(),
+            "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266"
+                .parse::<Address>()
+                .unwrap()
+        );
+        assert_eq!(
+            second.address(),
+            "0x70997970C51812dc3A010C7d01b50e0d17dc79C8"
+                .parse::<Address>()
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn should_generate_mnemonic() {
+        for word_count in WORD_COUNTS {
+            let phrase = generate_mnemonic(word_count).unwrap();
+
+            assert_eq!(phrase.split_whitespace().count(), word_count);
+            assert!(derive_wallet(&phrase, 0).is_ok());
+        }
+        assert!(generate_mnemonic(15).is_err());
+    }
+}
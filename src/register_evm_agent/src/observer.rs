@@ -0,0 +1,91 @@
+use std::sync::Arc;
+
+use ethers_core::types::{Address, TxHash, U256};
+use evmc_did::registration_info::RegistrationInfo;
+
+use crate::error::Error;
+
+/// Stages of a registration, as reported to a `RegistrationObserver` when they fail.
+///
+/// Unlike `RegistrationStep` they are not saved, and cover the queries too
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegistrationStage {
+    /// fetching the chain id and the registration info from the EVMC
+    FetchInfo,
+    /// checking that the address is not registered yet
+    CheckRegistration,
+    /// checking a transaction signed elsewhere against the registration info and the nonce
+    CheckTransaction,
+    /// fetching the nonce of the wallet
+    FetchNonce,
+    /// choosing the gas and signing the registration transaction
+    SignTransaction,
+    /// checking the wallet balance and minting the missing tokens
+    Fund,
+    /// submitting the registration transaction
+    RegisterAgent,
+    /// verifying the registration
+    VerifyRegistration,
+}
+
+/// Receives the progress of a `RegistrationService`, to display or forward it.
+///
+/// Every callback does nothing by default. Callbacks are called by the registration task,
+/// so they should return quickly
+pub trait RegistrationObserver: Send + Sync {
+    /// The registration info has been fetched from the EVMC, or set on the builder
+    fn info_fetched(&self, _registration_info: &RegistrationInfo) {}
+
+    /// The nonce of the registration transaction has been fetched
+    fn nonce_fetched(&self, _nonce: U256) {}
+
+    /// The registration transaction has been signed
+    fn transaction_signed(&self, _hash: TxHash) {}
+
+    /// `amount` native tokens have been minted to `address` to pay the registration
+    fn tokens_minted(&self, _address: Address, _amount: U256) {}
+
+    /// The registration transaction has been accepted by the EVMC
+    fn agent_registered(&self) {}
+
+    /// The registration has been verified; this is the last event of a successful registration
+    fn registration_verified(&self) {}
+
+    /// `stage` failed with `error`, after the retries allowed by the retry policy
+    fn step_failed(&self, _stage: RegistrationStage, _error: &Error) {}
+}
+
+/// Observer ignoring every event
+pub struct NoopObserver;
+
+impl RegistrationObserver for NoopObserver {}
+
+impl<T: RegistrationObserver + ?Sized> RegistrationObserver for Arc<T> {
+    fn info_fetched(&self, registration_info: &RegistrationInfo) {
+        (**self).info_fetched(registration_info)
+    }
+
+    fn nonce_fetched(&self, nonce: U256) {
+        (**self).nonce_fetched(nonce)
+    }
+
+    fn transaction_signed(&self, hash: TxHash) {
+        (**self).transaction_signed(hash)
+    }
+
+    fn tokens_minted(&self, address: Address, amount: U256) {
+        (**self).tokens_minted(address, amount)
+    }
+
+    fn agent_registered(&self) {
+        (**self).agent_registered()
+    }
+
+    fn registration_verified(&self) {
+        (**self).registration_verified()
+    }
+
+    fn step_failed(&self, stage: RegistrationStage, error: &Error) {
+        (**self).step_failed(stage, error)
+    }
+}
@@ -0,0 +1,154 @@
+use std::path::{Path, PathBuf};
+
+use candid::Principal;
+use ethers_core::types::{Address, Bytes};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+use crate::registration::RegistrationTransaction;
+
+/// Steps of the registration, in the order they are completed
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RegistrationStep {
+    #[default]
+    NotStarted,
+    /// the address is not registered yet and the wallet has been funded, if requested
+    Funded,
+    /// `register_ic_agent` succeeded
+    AgentRegistered,
+    /// `verify_registration` succeeded
+    Verified,
+}
+
+/// Local record of the registration progress of a wallet address for a canister on an EVMC
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegistrationProgress {
+    #[serde(skip)]
+    path: PathBuf,
+    evmc_canister_id: Principal,
+    register_canister_id: Principal,
+    address: Address,
+    step: RegistrationStep,
+    /// RLP encoded registration transaction, kept until the agent is registered
+    transaction: Option<Bytes>,
+}
+
+impl RegistrationProgress {
+    /// Load the progress record stored in `dir`, or start a new one if there is none
+    pub fn load(
+        dir: &Path,
+        evmc_canister_id: Principal,
+        register_canister_id: Principal,
+        address: Address,
+    ) -> Result<Self> {
+        let path = dir.join(format!(
+            "{evmc_canister_id}_{register_canister_id}_{address:?}.json"
+        ));
+        if !path.exists() {
+            return Ok(Self {
+                path,
+                evmc_canister_id,
+                register_canister_id,
+                address,
+                step: RegistrationStep::default(),
+                transaction: None,
+            });
+        }
+
+        let progress_error = |e: String| Error::Progress(path.clone(), e);
+        let json = std::fs::read_to_string(&path).map_err(|e| progress_error(e.to_string()))?;
+        let mut progress: Self =
+            serde_json::from_str(&json).map_err(|e| progress_error(e.to_string()))?;
+        progress.path = path;
+
+        Ok(progress)
+    }
+
+    /// Returns the last completed step
+    pub fn step(&self) -> RegistrationStep {
+        self.step
+    }
+
+    /// Mark `step` as completed and save the record
+    pub fn set_step(&mut self, step: RegistrationStep) -> Result<()> {
+        self.step = step;
+        if step >= RegistrationStep::AgentRegistered {
+            self.transaction = None;
+        }
+        self.save()
+    }
+
+    /// Returns the registration transaction of a previous attempt, if any
+    pub fn registration_transaction(&self) -> Option<Result<RegistrationTransaction>> {
+        self.transaction
+            .clone()
+            .map(RegistrationTransaction::decode)
+    }
+
+    /// Store the registration transaction which is being submitted and save the record
+    pub fn set_registration_transaction(
+        &mut self,
+        tx: Option<&RegistrationTransaction>,
+    ) -> Result<()> {
+        self.transaction = tx.map(|tx| tx.rlp.clone());
+        self.save()
+    }
+
+    /// Returns the path of the record
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn save(&self) -> Result<()> {
+        let progress_error = |e: String| Error::Progress(self.path.clone(), e);
+
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| progress_error(e.to_string()))?;
+        }
+        let json = serde_json::to_string_pretty(self).map_err(|e| progress_error(e.to_string()))?;
+        std::fs::write(&self.path, json).map_err(|e| progress_error(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    const EVMC_ID: &str = "4fe7g-7iaaa-aaaak-aegcq-cai";
+    const CANISTER_ID: &str = "chu2x-jyaaa-aaaah-aaqra-cai";
+    const ADDRESS: &str = "0x0e571b5fcd9f92e957c24c6357dab14b2d2344e4";
+
+    fn load(dir: &Path) -> RegistrationProgress {
+        RegistrationProgress::load(
+            dir,
+            Principal::from_text(EVMC_ID).unwrap(),
+            Principal::from_text(CANISTER_ID).unwrap(),
+            ADDRESS.parse().unwrap(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn should_start_new_progress() {
+        let dir = std::env::temp_dir().join("register_evm_agent_progress_new");
+
+        let progress = load(&dir);
+
+        assert_eq!(progress.step(), RegistrationStep::NotStarted);
+        assert!(progress.registration_transaction().is_none());
+        assert!(!progress.path().exists());
+    }
+
+    #[test]
+    fn should_save_and_load_progress() {
+        let dir = std::env::temp_dir().join("register_evm_agent_progress_saved");
+
+        let mut progress = load(&dir);
+        progress.set_step(RegistrationStep::Funded).unwrap();
+        let loaded = load(&dir);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(loaded.step(), RegistrationStep::Funded);
+    }
+}
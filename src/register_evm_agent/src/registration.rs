@@ -1,206 +1,1509 @@
-use candid::{Decode, Encode, Principal};
+use async_trait::async_trait;
+use candid::Principal;
 use eth_signer::{Signer, Wallet};
 use ethers_core::k256::ecdsa::SigningKey;
 use ethers_core::types::transaction::eip2718::TypedTransaction;
-use ethers_core::types::TransactionRequest;
-use evmc_did::error::EvmError;
+use ethers_core::types::{
+    Address, Bytes, Eip1559TransactionRequest, Signature, TransactionRequest,
+};
+use evmc_did::error::{EvmError, TransactionPoolError};
 use evmc_did::registration_info::RegistrationInfo;
-use evmc_did::{BasicAccount, Transaction, H160};
-use ic_agent::Agent;
+use evmc_did::{Transaction, H160, U256};
 
-use crate::agent::user_principal;
-use crate::constant::{
-    METHOD_ACCOUNT_BASIC, METHOD_ADDRESS_REGISTERED, METHOD_MINT_NATIVE_TOKENS,
-    METHOD_REGISTER_IC_AGENT, METHOD_REGISTRATION_IC_AGENT_INFO, METHOD_VERIFY_REGISTRATION,
-};
 use crate::error::{Error, Result};
+use crate::evmc::methods::{
+    ACCOUNT_BASIC, ETH_CHAIN_ID, ETH_ESTIMATE_GAS, GET_MIN_GAS_PRICE, IS_ADDRESS_REGISTERED,
+    MINT_EVM_TOKENS, REGISTER_IC_AGENT, REGISTRATION_IC_AGENT_INFO, VERIFY_REGISTRATION,
+};
+use crate::evmc::EvmcClient;
+use crate::gas::{GasStrategy, TransactionFees, TransactionGas};
+use crate::observer::{NoopObserver, RegistrationObserver, RegistrationStage};
+use crate::progress::{RegistrationProgress, RegistrationStep};
+use crate::retry::RetryPolicy;
 
-pub struct RegistrationService<'a> {
-    agent: Agent,
+pub struct RegistrationService<'a, C> {
+    address: Address,
     amount_to_mint: Option<u64>,
     chain_id: u64,
-    evmc_canister_id: Principal,
+    client: C,
+    gas_strategy: GasStrategy,
+    observer: Box<dyn RegistrationObserver + 'a>,
     register_canister_id: Principal,
     registration_info: RegistrationInfo,
-    wallet: Wallet<'a, SigningKey>,
+    retry_policy: RetryPolicy,
+    wallet: Option<Wallet<'a, SigningKey>>,
 }
 
-impl<'a> RegistrationService<'a> {
-    pub async fn new(
-        agent: Agent,
-        amount_to_mint: Option<u64>,
-        chain_id: u64,
-        evmc_canister_id: Principal,
-        register_canister_id: Principal,
-        wallet: Wallet<'a, SigningKey>,
-    ) -> Result<RegistrationService<'a>> {
-        info!("collecting registration info");
-        let registration_info = Self::get_registration_info(&agent, &evmc_canister_id).await?;
-        info!("registration service initialized");
+impl<'a, C: EvmcClient> RegistrationService<'a, C> {
+    /// Returns a builder of the service
+    pub fn builder() -> RegistrationServiceBuilder<'a, C> {
+        RegistrationServiceBuilder::default()
+    }
 
-        Ok(Self {
-            agent,
-            amount_to_mint,
-            chain_id,
-            evmc_canister_id,
-            register_canister_id,
-            registration_info,
-            wallet,
+    pub async fn register(&self) -> Result<()> {
+        // the registration is checked first, so that no transaction is built for nothing
+        self.observe(
+            RegistrationStage::CheckRegistration,
+            self.check_not_registered().await,
+        )?;
+        let tx = self.registration_transaction().await?;
+        self.register_agent(tx).await?;
+        self.verify_registration().await
+    }
+
+    /// Registers the agent with a registration transaction signed elsewhere, without verifying
+    /// the registration: the verification needs the wallet signing key, which the service
+    /// may not have; see `verify_registration`.
+    ///
+    /// The transaction is checked against the EVMC before any token is minted for it
+    pub async fn submit_registration(&self, tx: RegistrationTransaction) -> Result<()> {
+        self.observe(
+            RegistrationStage::CheckRegistration,
+            self.check_not_registered().await,
+        )?;
+        self.observe(
+            RegistrationStage::CheckTransaction,
+            self.check_transaction(&tx).await,
+        )?;
+        self.register_agent(tx).await
+    }
+
+    /// Checks that `tx` is sent by the wallet with its current nonce, for the EVMC chain id,
+    /// and pays the current registration fee to the current minter
+    async fn check_transaction(&self, tx: &RegistrationTransaction) -> Result<()> {
+        let transaction = &tx.transaction;
+        let mismatch = |field: &str, expected: String, actual: String| {
+            Err(Error::InvalidRegistrationTransaction(format!(
+                "{field} is {actual}, expected {expected}"
+            )))
+        };
+
+        if transaction.from != self.address {
+            return mismatch(
+                "sender",
+                format!("{:?}", self.address),
+                format!("{:?}", transaction.from),
+            );
+        }
+        let minter_address =
+            ethers_core::types::H160::from(self.registration_info.minter_address.clone());
+        if transaction.to != Some(minter_address) {
+            return mismatch(
+                "recipient",
+                format!("{minter_address:?}"),
+                format!("{:?}", transaction.to),
+            );
+        }
+        let registration_fee = self.registration_info.registration_fee;
+        if transaction.value != registration_fee.into() {
+            return mismatch(
+                "value",
+                registration_fee.to_string(),
+                transaction.value.to_string(),
+            );
+        }
+        if transaction.chain_id != Some(self.chain_id.into()) {
+            return mismatch(
+                "chain id",
+                self.chain_id.to_string(),
+                format!("{:?}", transaction.chain_id),
+            );
+        }
+
+        let address = H160::from(self.address);
+        let nonce: ethers_core::types::U256 = self
+            .retry_policy
+            .run(ACCOUNT_BASIC.name, |_| {
+                self.client.account_basic(address.clone())
+            })
+            .await?
+            .nonce
+            .into();
+        if transaction.nonce != nonce {
+            return mismatch("nonce", nonce.to_string(), transaction.nonce.to_string());
+        }
+
+        Ok(())
+    }
+
+    /// Funds and registers an address known not to be registered
+    async fn register_agent(&self, tx: RegistrationTransaction) -> Result<()> {
+        self.observe(RegistrationStage::Fund, self.fund_registration(&tx).await)?;
+        self.observe(
+            RegistrationStage::RegisterAgent,
+            self.register_ic_agent(tx).await,
+        )?;
+        self.observer.agent_registered();
+
+        Ok(())
+    }
+
+    /// Returns the wallet, failing if the service only knows its address
+    fn wallet(&self) -> Result<&Wallet<'a, SigningKey>> {
+        self.wallet.as_ref().ok_or_else(|| {
+            Error::InvalidRegistrationService("the wallet signing key is not set".to_string())
         })
     }
 
-    pub async fn register(&self) -> Result<()> {
-        self.register_ic_agent().await?;
+    /// Registers the agent, skipping the steps already completed according to `progress`.
+    ///
+    /// `progress` is saved after every completed step, so a failed registration
+    /// can be resumed by calling this method again
+    pub async fn resume_registration(&self, progress: &mut RegistrationProgress) -> Result<()> {
+        if progress.step() == RegistrationStep::Verified {
+            if self.is_address_registered().await? {
+                info!("registration already completed");
+                return Err(Error::AlreadyRegistered(self.register_canister_id));
+            }
+            // the EVMC doesn't know the registration, e.g. a local replica restarted
+            // with `dfx start --clean` reusing the canister id: the record is stale
+            info!("registration not found on the EVMC, starting over");
+            progress.set_step(RegistrationStep::NotStarted)?;
+        }
+
+        if progress.step() < RegistrationStep::Funded {
+            self.observe(
+                RegistrationStage::CheckRegistration,
+                self.check_not_registered().await,
+            )?;
+            let tx = self.progress_transaction(progress).await?;
+            self.observe(RegistrationStage::Fund, self.fund_registration(&tx).await)?;
+            progress.set_step(RegistrationStep::Funded)?;
+        }
+
+        if progress.step() < RegistrationStep::AgentRegistered {
+            let tx = self.progress_transaction(progress).await?;
+
+            let result = match self.register_ic_agent(tx).await {
+                Err(Error::Evm(EvmError::TransactionPool(
+                    TransactionPoolError::TransactionAlreadyExists,
+                ))) => {
+                    info!("registration transaction was already submitted");
+                    Ok(())
+                }
+                result => result,
+            };
+            let result = self.observe(RegistrationStage::RegisterAgent, result);
+            if matches!(result, Err(Error::Evm(_))) {
+                // the transaction was rejected, a new one must be signed
+                progress.set_registration_transaction(None)?;
+            }
+            result?;
+            self.observer.agent_registered();
+            progress.set_step(RegistrationStep::AgentRegistered)?;
+        }
+
         self.verify_registration().await?;
+        progress.set_step(RegistrationStep::Verified)?;
 
         Ok(())
     }
 
-    async fn register_ic_agent(&self) -> Result<()> {
-        let principal = user_principal(&self.agent)?;
+    /// Reports the error of a failed `stage` to the observer
+    fn observe<T>(&self, stage: RegistrationStage, result: Result<T>) -> Result<T> {
+        if let Err(err) = &result {
+            self.observer.step_failed(stage, err);
+        }
+        result
+    }
+
+    /// Returns the registration transaction saved in `progress`, or signs and saves a new one.
+    /// The transaction of a previous attempt is reused, since it may have been applied already
+    async fn progress_transaction(
+        &self,
+        progress: &mut RegistrationProgress,
+    ) -> Result<RegistrationTransaction> {
+        match progress.registration_transaction() {
+            Some(tx) => tx,
+            None => {
+                let tx = self.registration_transaction().await?;
+                progress.set_registration_transaction(Some(&tx))?;
+                Ok(tx)
+            }
+        }
+    }
+
+    async fn check_not_registered(&self) -> Result<()> {
+        let principal = self.client.principal()?;
         info!("registering ic-agent {principal}");
         let is_registered = self.is_address_registered().await?;
         if is_registered {
             info!("agent is already registered");
-            return Err(Error::AlreadyRegistered(principal));
+            return Err(Error::AlreadyRegistered(self.register_canister_id));
         }
 
-        let tx = self.registration_transaction().await?;
-        let args = Encode!(&Transaction::from(tx), &self.register_canister_id)?;
+        Ok(())
+    }
+
+    /// Checks that the wallet balance covers the cost of `tx`.
+    /// If it doesn't, the shortfall is minted when minting is enabled (only on testnets),
+    /// up to the amount to mint.
+    ///
+    /// A mint whose outcome is unknown is not sent again before its ingress expiry:
+    /// in the meantime the balance is read again to find whether it was applied
+    async fn fund_registration(&self, tx: &RegistrationTransaction) -> Result<()> {
+        let needed = tx.cost();
+        let available = self
+            .retry_policy
+            .run(ACCOUNT_BASIC.name, |_| self.balance())
+            .await?;
+        info!("registration cost: {needed}, balance: {available}");
 
-        // mint tokens to be able to pay registration fee (only on testnets)
-        if let Some(amount_to_mint) = self.amount_to_mint {
-            info!("minting native tokens for address");
-            self.mint_native_tokens_to_address(amount_to_mint).await?;
+        if available >= needed {
+            return Ok(());
         }
 
-        let res = self
-            .agent
-            .update(&self.evmc_canister_id, METHOD_REGISTER_IC_AGENT)
-            .with_arg(args)
-            .call_and_wait()
-            .await?;
+        let shortfall = needed - available;
+        match self.amount_to_mint {
+            Some(amount_to_mint) if shortfall <= ethers_core::types::U256::from(amount_to_mint) => {
+                info!("minting {shortfall} native tokens for address");
+                self.retry_policy
+                    .run_update(
+                        MINT_EVM_TOKENS.name,
+                        || self.mint_native_tokens_to_address(shortfall),
+                        || async move { Ok((self.balance().await? >= needed).then_some(())) },
+                    )
+                    .await?;
+                self.observer.tokens_minted(self.address, shortfall);
+
+                Ok(())
+            }
+            _ => Err(Error::InsufficientFunds { needed, available }),
+        }
+    }
 
-        info!("{METHOD_REGISTER_IC_AGENT} called, decoding result");
-        Decode!(res.as_slice(), std::result::Result<(), EvmError>)??;
-        info!("result is OK");
+    async fn balance(&self) -> Result<ethers_core::types::U256> {
+        let address = H160::from(self.address);
+        Ok(self.client.account_basic(address).await?.balance.into())
+    }
+
+    /// Submits the registration transaction.
+    ///
+    /// Before a retry the registration state is checked, since the failed attempt
+    /// may have been applied by the EVMC anyway
+    async fn register_ic_agent(&self, tx: RegistrationTransaction) -> Result<()> {
+        self.retry_policy
+            .run(REGISTER_IC_AGENT.name, |attempt| {
+                let tx = tx.clone();
+                async move {
+                    if attempt == 1 {
+                        return self.try_register_ic_agent(tx).await;
+                    }
+
+                    if self.try_is_address_registered().await? {
+                        info!("agent registered by a previous attempt");
+                        return Ok(());
+                    }
+                    match self.try_register_ic_agent(tx).await {
+                        Err(Error::Evm(EvmError::TransactionPool(
+                            TransactionPoolError::TransactionAlreadyExists,
+                        ))) => {
+                            info!("registration transaction submitted by a previous attempt");
+                            Ok(())
+                        }
+                        result => result,
+                    }
+                }
+            })
+            .await
+    }
+
+    async fn try_register_ic_agent(&self, tx: RegistrationTransaction) -> Result<()> {
+        self.client
+            .register_ic_agent(evmc_transaction(tx.transaction), self.register_canister_id)
+            .await
+    }
+
+    /// Verifies the registration of the agent, sending the wallet signing key to the EVMC.
+    ///
+    /// The EVMC can't be queried for the verification, so after a transient error
+    /// the verification is only sent again once the failed attempt has expired
+    pub async fn verify_registration(&self) -> Result<()> {
+        let result = self
+            .retry_policy
+            .run_update(
+                VERIFY_REGISTRATION.name,
+                || self.try_verify_registration(),
+                || async { Ok(None) },
+            )
+            .await;
+        self.observe(RegistrationStage::VerifyRegistration, result)?;
+        self.observer.registration_verified();
 
         Ok(())
     }
 
-    async fn verify_registration(&self) -> Result<()> {
+    async fn try_verify_registration(&self) -> Result<()> {
         info!("verifying registration...");
-        let args = Encode!(
-            &self.wallet.signer().to_bytes().to_vec(),
-            &self.register_canister_id
-        )?;
+        self.client
+            .verify_registration(
+                self.wallet()?.signer().to_bytes().to_vec(),
+                self.register_canister_id,
+            )
+            .await
+    }
 
-        let res = self
-            .agent
-            .update(&self.evmc_canister_id, METHOD_VERIFY_REGISTRATION)
-            .with_arg(args)
-            .call_and_wait()
-            .await?;
+    async fn is_address_registered(&self) -> Result<bool> {
+        self.retry_policy
+            .run(IS_ADDRESS_REGISTERED.name, |_| {
+                self.try_is_address_registered()
+            })
+            .await
+    }
+
+    async fn try_is_address_registered(&self) -> Result<bool> {
+        self.client
+            .is_address_registered(self.address.into(), self.register_canister_id)
+            .await
+    }
 
-        info!("{METHOD_VERIFY_REGISTRATION} called, decoding result");
+    /// Builds and signs the registration transaction, without submitting it
+    pub async fn registration_transaction(&self) -> Result<RegistrationTransaction> {
+        let wallet = self.wallet()?;
+        let to = ethers_core::types::H160::from(self.registration_info.minter_address.clone());
+        let address = self.address;
+
+        let evmc_address = H160::from(address);
+        let account = self
+            .retry_policy
+            .run(ACCOUNT_BASIC.name, |_| {
+                self.client.account_basic(evmc_address.clone())
+            })
+            .await;
+        let nonce: ethers_core::types::U256 = self
+            .observe(RegistrationStage::FetchNonce, account)?
+            .nonce
+            .into();
+        self.observer.nonce_fetched(nonce);
+
+        let gas = self.transaction_gas(address, to).await;
+        let gas = self.observe(RegistrationStage::SignTransaction, gas)?;
+        let tx = sign_registration_transaction(
+            wallet,
+            to,
+            self.registration_info.registration_fee,
+            nonce,
+            self.chain_id,
+            gas,
+        )
+        .await;
+        let tx = self.observe(RegistrationStage::SignTransaction, tx)?;
+        self.observer.transaction_signed(tx.transaction.hash);
+
+        Ok(tx)
+    }
+
+    /// Returns the gas of the registration transaction,
+    /// querying the EVMC for the values not set by the gas strategy
+    async fn transaction_gas(&self, from: Address, to: Address) -> Result<TransactionGas> {
+        let fees = match self.gas_strategy.fees {
+            Some(fees) => fees,
+            None => TransactionFees::Legacy {
+                gas_price: self
+                    .retry_policy
+                    .run(GET_MIN_GAS_PRICE.name, |_| self.client.min_gas_price())
+                    .await?,
+            },
+        };
+        let gas = match self.gas_strategy.gas {
+            Some(gas) => gas,
+            None => {
+                self.retry_policy
+                    .run(ETH_ESTIMATE_GAS.name, |_| {
+                        self.client.estimate_gas(
+                            from,
+                            to,
+                            self.registration_info.registration_fee.into(),
+                        )
+                    })
+                    .await?
+            }
+        };
 
-        Decode!(res.as_slice(), std::result::Result<(), EvmError>)??;
+        Ok(TransactionGas { gas, fees })
+    }
 
-        info!("result is OK");
+    async fn mint_native_tokens_to_address(
+        &self,
+        amount_to_mint: ethers_core::types::U256,
+    ) -> Result<()> {
+        let address = H160::from(self.address);
+        info!("minting EVM tokens to {address}");
+        self.client
+            .mint_native_tokens(address, amount_to_mint.into())
+            .await?;
+        info!("tokens minted");
 
         Ok(())
     }
 
-    async fn is_address_registered(&self) -> Result<bool> {
-        let address: H160 = self.wallet.address().into();
-        info!("checking if {address} is already registered...");
-        let args = Encode!(&address, &self.register_canister_id)?;
-        let res = self
-            .agent
-            .query(&self.evmc_canister_id, METHOD_ADDRESS_REGISTERED)
-            .with_arg(args)
-            .call()
+    /// Returns the registration status of `address` for `register_canister_id`,
+    /// together with the wallet account and the current registration info
+    pub async fn status(
+        client: &C,
+        register_canister_id: &Principal,
+        address: &H160,
+    ) -> Result<RegistrationStatus> {
+        let registered = client
+            .is_address_registered(address.clone(), *register_canister_id)
             .await?;
-        let principal = user_principal(&self.agent)?;
-        match Decode!(res.as_slice(), bool) {
-            Ok(res) => {
-                info!("{address} is not registered yet");
-                Ok(res)
+        let account = client.account_basic(address.clone()).await?;
+        let registration_info = client.registration_info().await?;
+
+        Ok(RegistrationStatus {
+            address: address.clone(),
+            register_canister_id: *register_canister_id,
+            registered,
+            balance: account.balance,
+            nonce: account.nonce,
+            registration_info,
+        })
+    }
+}
+
+/// Builder of a `RegistrationService`.
+///
+/// The EVMC client, the canister to register and either the wallet or its address must be set.
+/// `build` validates the inputs and checks the chain id against the EVMC one
+pub struct RegistrationServiceBuilder<'a, C> {
+    address: Option<Address>,
+    amount_to_mint: Option<u64>,
+    chain_id: Option<u64>,
+    client: Option<C>,
+    gas_strategy: GasStrategy,
+    observer: Box<dyn RegistrationObserver + 'a>,
+    register_canister_id: Option<Principal>,
+    registration_info: Option<RegistrationInfo>,
+    retry_policy: RetryPolicy,
+    wallet: Option<Wallet<'a, SigningKey>>,
+}
+
+impl<C> Default for RegistrationServiceBuilder<'_, C> {
+    fn default() -> Self {
+        Self {
+            address: None,
+            amount_to_mint: None,
+            chain_id: None,
+            client: None,
+            gas_strategy: GasStrategy::default(),
+            observer: Box::new(NoopObserver),
+            register_canister_id: None,
+            registration_info: None,
+            retry_policy: RetryPolicy::default(),
+            wallet: None,
+        }
+    }
+}
+
+impl<'a, C: EvmcClient> RegistrationServiceBuilder<'a, C> {
+    /// Sets the client of the EVMC the canister is registered to
+    pub fn evmc(mut self, client: C) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    /// Sets the principal of the canister to register
+    pub fn target_canister(mut self, register_canister_id: Principal) -> Self {
+        self.register_canister_id = Some(register_canister_id);
+        self
+    }
+
+    /// Sets the chain id of the registration transaction; by default it's the EVMC chain id
+    pub fn chain_id(mut self, chain_id: u64) -> Self {
+        self.chain_id = Some(chain_id);
+        self
+    }
+
+    /// Sets the wallet to register
+    pub fn wallet(mut self, wallet: Wallet<'a, SigningKey>) -> Self {
+        self.wallet = Some(wallet);
+        self
+    }
+
+    /// Sets the address of the wallet to register, instead of the wallet, when its signing key
+    /// is not available: the service can then only submit a transaction signed elsewhere
+    pub fn address(mut self, address: Address) -> Self {
+        self.address = Some(address);
+        self
+    }
+
+    /// Sets the maximum amount of native tokens minted to the wallet when its balance
+    /// doesn't cover the registration cost; `None`, the default, disables minting
+    pub fn amount_to_mint(mut self, amount_to_mint: Option<u64>) -> Self {
+        self.amount_to_mint = amount_to_mint;
+        self
+    }
+
+    /// Sets how the gas of the registration transaction is chosen;
+    /// by default both the gas limit and the gas price are queried from the EVMC
+    pub fn gas_strategy(mut self, gas_strategy: GasStrategy) -> Self {
+        self.gas_strategy = gas_strategy;
+        self
+    }
+
+    /// Sets how the calls to the EVMC are retried on transient errors
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Sets the observer notified of the progress of the registration;
+    /// by default the events are ignored
+    pub fn observer(mut self, observer: impl RegistrationObserver + 'a) -> Self {
+        self.observer = Box::new(observer);
+        self
+    }
+
+    /// Sets the minter address and the registration fee, so that they are not fetched
+    /// from the EVMC
+    pub fn registration_info(mut self, registration_info: RegistrationInfo) -> Self {
+        self.registration_info = Some(registration_info);
+        self
+    }
+
+    /// Validates the inputs, then checks the chain id and fetches the registration info,
+    /// unless already set, from the EVMC
+    pub async fn build(self) -> Result<RegistrationService<'a, C>> {
+        let client = self.client.ok_or_else(|| {
+            Error::InvalidRegistrationService("the EVMC client is not set".to_string())
+        })?;
+        let register_canister_id = self.register_canister_id.ok_or_else(|| {
+            Error::InvalidRegistrationService("the target canister is not set".to_string())
+        })?;
+        if register_canister_id == Principal::anonymous()
+            || register_canister_id == Principal::management_canister()
+        {
+            return Err(Error::InvalidRegistrationService(format!(
+                "{register_canister_id} is not a valid target canister"
+            )));
+        }
+        let address = match (&self.wallet, self.address) {
+            (Some(wallet), None) => wallet.address(),
+            (None, Some(address)) => address,
+            (Some(_), Some(_)) => {
+                return Err(Error::InvalidRegistrationService(
+                    "only one of the wallet and its address can be set".to_string(),
+                ))
             }
-            Err(_) => Err(Error::CouldNotCheckRegistrationStatus(
-                address.to_hex_str(),
-                principal,
-            )),
+            (None, None) => {
+                return Err(Error::InvalidRegistrationService(
+                    "the wallet is not set".to_string(),
+                ))
+            }
+        };
+        if self.amount_to_mint == Some(0) {
+            return Err(Error::InvalidRegistrationService(
+                "the amount to mint must be positive".to_string(),
+            ));
+        }
+        if self.retry_policy.max_attempts == 0 {
+            return Err(Error::InvalidRegistrationService(
+                "the retry policy must allow at least one attempt".to_string(),
+            ));
+        }
+
+        let retry_policy = self.retry_policy;
+        let observer = self.observer;
+        let (chain_id, registration_info) = (self.chain_id, self.registration_info);
+        let result = async {
+            let evmc_chain_id = retry_policy
+                .run(ETH_CHAIN_ID.name, |_| client.chain_id())
+                .await?;
+            let chain_id = chain_id.unwrap_or(evmc_chain_id);
+            if chain_id != evmc_chain_id {
+                return Err(Error::ChainIdMismatch(chain_id, evmc_chain_id));
+            }
+
+            let registration_info = match registration_info {
+                Some(registration_info) => registration_info,
+                None => {
+                    info!("collecting registration info");
+                    retry_policy
+                        .run(REGISTRATION_IC_AGENT_INFO.name, |_| {
+                            client.registration_info()
+                        })
+                        .await?
+                }
+            };
+
+            Ok((chain_id, registration_info))
+        }
+        .await;
+        if let Err(err) = &result {
+            observer.step_failed(RegistrationStage::FetchInfo, err);
         }
+        let (chain_id, registration_info) = result?;
+        observer.info_fetched(&registration_info);
+        info!("registration service initialized");
+
+        Ok(RegistrationService {
+            address,
+            amount_to_mint: self.amount_to_mint,
+            chain_id,
+            client,
+            gas_strategy: self.gas_strategy,
+            observer,
+            register_canister_id,
+            registration_info,
+            retry_policy,
+            wallet: self.wallet,
+        })
     }
+}
 
-    async fn registration_transaction(&self) -> Result<ethers_core::types::Transaction> {
-        let to = ethers_core::types::H160::from(self.registration_info.minter_address.clone());
-        let address = self.wallet.address();
+/// Signer of the registration transaction
+#[async_trait]
+pub trait TransactionSigner: Send + Sync {
+    /// Address of the signing wallet
+    fn signer_address(&self) -> Address;
 
-        let args = Encode!(&H160::from(address))?;
+    /// Signs the transaction, returning the reason of a failure
+    async fn sign(&self, tx: &TypedTransaction) -> std::result::Result<Signature, String>;
+}
 
-        let res = self
-            .agent
-            .query(&self.evmc_canister_id, METHOD_ACCOUNT_BASIC)
-            .with_arg(args)
-            .call()
-            .await?;
+#[async_trait]
+impl TransactionSigner for Wallet<'_, SigningKey> {
+    fn signer_address(&self) -> Address {
+        self.address()
+    }
 
-        let nonce = Decode!(res.as_slice(), BasicAccount)?.nonce;
+    async fn sign(&self, tx: &TypedTransaction) -> std::result::Result<Signature, String> {
+        self.sign_transaction(tx).await.map_err(|e| e.to_string())
+    }
+}
 
-        info!("creating registration transaction (from: {address}, to: {to}, value: {}, nonce: {nonce}, gas_price: 0, gas: 53000)", self.registration_info.registration_fee);
+/// Builds and signs the registration transaction which pays `registration_fee` to `minter_address`.
+///
+/// No network access is needed, so this can run on an offline machine
+pub async fn sign_registration_transaction(
+    wallet: &impl TransactionSigner,
+    minter_address: Address,
+    registration_fee: u64,
+    nonce: ethers_core::types::U256,
+    chain_id: u64,
+    gas: TransactionGas,
+) -> Result<RegistrationTransaction> {
+    let address = wallet.signer_address();
+    info!("creating registration transaction (from: {address}, to: {minter_address}, value: {registration_fee}, nonce: {nonce}, {gas:?})");
 
-        let tx: TypedTransaction = TransactionRequest::new()
+    let TransactionGas { gas, fees } = gas;
+    let tx: TypedTransaction = match fees {
+        TransactionFees::Legacy { gas_price } => TransactionRequest::new()
             .from(address)
-            .to(to)
-            .value(self.registration_info.registration_fee)
-            .chain_id(self.chain_id)
+            .to(minter_address)
+            .value(registration_fee)
+            .chain_id(chain_id)
             .nonce(nonce)
-            .gas_price(0)
-            .gas(53000)
-            .into();
-        let signature = self.wallet.sign_transaction(&tx).await.unwrap();
-        let bytes = tx.rlp_signed(&signature);
-        let mut tx: ethers_core::types::Transaction = rlp::decode(&bytes).unwrap();
-        tx.from = address;
+            .gas_price(gas_price)
+            .gas(gas)
+            .into(),
+        TransactionFees::Eip1559 {
+            max_fee_per_gas,
+            max_priority_fee_per_gas,
+        } => Eip1559TransactionRequest::new()
+            .from(address)
+            .to(minter_address)
+            .value(registration_fee)
+            .chain_id(chain_id)
+            .nonce(nonce)
+            .max_fee_per_gas(max_fee_per_gas)
+            .max_priority_fee_per_gas(max_priority_fee_per_gas)
+            .gas(gas)
+            .into(),
+    };
+    let signature = wallet.sign(&tx).await.map_err(Error::Signing)?;
 
-        Ok(tx)
+    // the transaction must survive the RLP round trip with the signer as sender
+    let registration_tx = RegistrationTransaction::decode(tx.rlp_signed(&signature))?;
+    if registration_tx.transaction.from != address {
+        return Err(Error::TransactionSignature(format!(
+            "recovered sender {:?} doesn't match the signer {address:?}",
+            registration_tx.transaction.from
+        )));
     }
 
-    async fn mint_native_tokens_to_address(&self, amount_to_mint: u64) -> Result<()> {
-        let address = H160::from(self.wallet.address());
-        info!("minting EVM tokens to {address}");
-        let payload = Encode!(&address, &evmc_did::U256::from(amount_to_mint))?;
+    Ok(registration_tx)
+}
 
-        let res = self
-            .agent
-            .update(&self.evmc_canister_id, METHOD_MINT_NATIVE_TOKENS)
-            .with_arg(payload)
-            .call_and_wait()
-            .await?;
+/// Converts a signed transaction to the EVMC one, keeping the EIP-1559 fields
+fn evmc_transaction(transaction: ethers_core::types::Transaction) -> Transaction {
+    let max_fee_per_gas = transaction.max_fee_per_gas;
+    let max_priority_fee_per_gas = transaction.max_priority_fee_per_gas;
+    let transaction_type = transaction.transaction_type;
 
-        Decode!(res.as_slice(), std::result::Result<evmc_did::U256, EvmError>)??;
+    let mut evmc_transaction = Transaction::from(transaction);
+    evmc_transaction.max_fee_per_gas = max_fee_per_gas.map(Into::into);
+    evmc_transaction.max_priority_fee_per_gas = max_priority_fee_per_gas.map(Into::into);
+    evmc_transaction.transaction_type = transaction_type.map(Into::into);
+    evmc_transaction
+}
 
-        info!("tokens minted");
+/// Registration state of a wallet address for a canister
+#[derive(Debug, Clone)]
+pub struct RegistrationStatus {
+    /// wallet address
+    pub address: H160,
+    /// principal of the registered canister
+    pub register_canister_id: Principal,
+    /// whether the address is registered for the canister
+    pub registered: bool,
+    /// wallet balance
+    pub balance: U256,
+    /// wallet nonce
+    pub nonce: U256,
+    /// current minter address and registration fee
+    pub registration_info: RegistrationInfo,
+}
 
-        Ok(())
+/// Signed registration transaction
+#[derive(Debug, Clone)]
+pub struct RegistrationTransaction {
+    /// decoded transaction
+    pub transaction: ethers_core::types::Transaction,
+    /// RLP encoded signed transaction
+    pub rlp: Bytes,
+}
+
+impl RegistrationTransaction {
+    /// Decodes a RLP encoded signed transaction, recovering its sender from the signature
+    pub fn decode(rlp: Bytes) -> Result<Self> {
+        let mut transaction: ethers_core::types::Transaction = rlp::decode(&rlp)?;
+        transaction
+            .recover_from_mut()
+            .map_err(|e| Error::TransactionSignature(e.to_string()))?;
+
+        Ok(Self { transaction, rlp })
     }
 
-    async fn get_registration_info(
-        agent: &Agent,
-        evmc_canister_id: &Principal,
-    ) -> Result<RegistrationInfo> {
-        let args = Encode!()?;
+    /// Maximum cost of the transaction: its value plus the gas limit times the gas price,
+    /// or the max fee per gas for EIP-1559 transactions
+    pub fn cost(&self) -> ethers_core::types::U256 {
+        let transaction = &self.transaction;
+        let gas_price = transaction
+            .max_fee_per_gas
+            .or(transaction.gas_price)
+            .unwrap_or_default();
 
-        let res = agent
-            .query(evmc_canister_id, METHOD_REGISTRATION_IC_AGENT_INFO)
-            .with_arg(args)
-            .call()
-            .await?;
-        match Decode!(res.as_slice(), RegistrationInfo) {
-            Ok(res) => Ok(res),
-            Err(e) => Err(Error::CouldNotGetRegistrationInfo(e.to_string())),
+        transaction
+            .gas
+            .saturating_mul(gas_price)
+            .saturating_add(transaction.value)
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use evmc_did::BasicAccount;
+    use mockall::predicate::{always, eq};
+    use mockall::Sequence;
+
+    use super::*;
+    use crate::evmc::MockEvmcClient;
+
+    const REGISTRATION_FEE: u64 = 100_000;
+    const GAS_PRICE: u64 = 10;
+    const CHAIN_ID: u64 = 355113;
+
+    fn register_canister_id() -> Principal {
+        Principal::from_text("chu2x-jyaaa-aaaah-aaqra-cai").unwrap()
+    }
+
+    /// principal of the IC identity calling the EVMC
+    fn identity_principal() -> Principal {
+        Principal::from_text("4fe7g-7iaaa-aaaak-aegcq-cai").unwrap()
+    }
+
+    fn registration_info() -> RegistrationInfo {
+        RegistrationInfo {
+            minter_address: H160::from(
+                "0xb0e5863d0ddf7e105e409fee0ecc0123a362e14b"
+                    .parse::<Address>()
+                    .unwrap(),
+            ),
+            registration_fee: REGISTRATION_FEE,
+        }
+    }
+
+    /// Mock answering the queries of a registration: the wallet has `balance` native tokens
+    fn mock_client(balance: u64) -> MockEvmcClient {
+        let mut client = MockEvmcClient::new();
+        client
+            .expect_principal()
+            .returning(|| Ok(identity_principal()));
+        client.expect_chain_id().returning(|| Ok(CHAIN_ID));
+        client
+            .expect_registration_info()
+            .returning(|| Ok(registration_info()));
+        client.expect_account_basic().returning(move |_| {
+            Ok(BasicAccount {
+                balance: balance.into(),
+                nonce: U256::zero(),
+            })
+        });
+        client
+    }
+
+    fn registration_service_builder(
+        client: MockEvmcClient,
+        amount_to_mint: Option<u64>,
+    ) -> RegistrationServiceBuilder<'static, MockEvmcClient> {
+        RegistrationService::builder()
+            .evmc(client)
+            .target_canister(register_canister_id())
+            .chain_id(CHAIN_ID)
+            .wallet(Wallet::new(&mut rand::thread_rng()))
+            .amount_to_mint(amount_to_mint)
+            .gas_strategy(GasStrategy {
+                gas: Some(21000.into()),
+                fees: Some(TransactionFees::Legacy {
+                    gas_price: GAS_PRICE.into(),
+                }),
+            })
+    }
+
+    async fn registration_service(
+        client: MockEvmcClient,
+        amount_to_mint: Option<u64>,
+    ) -> RegistrationService<'static, MockEvmcClient> {
+        registration_service_builder(client, amount_to_mint)
+            .build()
+            .await
+            .unwrap()
+    }
+
+    /// Observer recording the events it receives
+    #[derive(Default)]
+    struct RecordingObserver(Mutex<Vec<String>>);
+
+    impl RecordingObserver {
+        fn record(&self, event: String) {
+            self.0.lock().unwrap().push(event);
+        }
+
+        fn events(&self) -> Vec<String> {
+            self.0.lock().unwrap().clone()
+        }
+    }
+
+    impl RegistrationObserver for RecordingObserver {
+        fn info_fetched(&self, registration_info: &RegistrationInfo) {
+            self.record(format!(
+                "info_fetched {}",
+                registration_info.registration_fee
+            ));
+        }
+
+        fn nonce_fetched(&self, nonce: ethers_core::types::U256) {
+            self.record(format!("nonce_fetched {nonce}"));
+        }
+
+        fn transaction_signed(&self, _hash: ethers_core::types::TxHash) {
+            self.record("transaction_signed".to_string());
+        }
+
+        fn tokens_minted(&self, _address: Address, amount: ethers_core::types::U256) {
+            self.record(format!("tokens_minted {amount}"));
+        }
+
+        fn agent_registered(&self) {
+            self.record("agent_registered".to_string());
+        }
+
+        fn registration_verified(&self) {
+            self.record("registration_verified".to_string());
+        }
+
+        fn step_failed(&self, stage: RegistrationStage, _error: &Error) {
+            self.record(format!("step_failed {stage:?}"));
+        }
+    }
+
+    #[tokio::test]
+    async fn should_reject_chain_id_mismatch() {
+        let result = RegistrationService::builder()
+            .evmc(mock_client(0))
+            .target_canister(register_canister_id())
+            .chain_id(1)
+            .wallet(Wallet::new(&mut rand::thread_rng()))
+            .build()
+            .await;
+
+        assert!(matches!(result, Err(Error::ChainIdMismatch(1, CHAIN_ID))));
+    }
+
+    #[tokio::test]
+    async fn should_validate_builder_inputs_before_calling_the_evmc() {
+        // the client has no expectations, so any call would fail the test
+        let result = RegistrationService::builder()
+            .evmc(MockEvmcClient::new())
+            .wallet(Wallet::new(&mut rand::thread_rng()))
+            .build()
+            .await;
+        assert!(matches!(result, Err(Error::InvalidRegistrationService(_))));
+
+        let result = RegistrationService::builder()
+            .evmc(MockEvmcClient::new())
+            .target_canister(Principal::anonymous())
+            .wallet(Wallet::new(&mut rand::thread_rng()))
+            .build()
+            .await;
+        assert!(matches!(result, Err(Error::InvalidRegistrationService(_))));
+
+        let result = RegistrationService::builder()
+            .evmc(MockEvmcClient::new())
+            .target_canister(register_canister_id())
+            .wallet(Wallet::new(&mut rand::thread_rng()))
+            .amount_to_mint(Some(0))
+            .build()
+            .await;
+        assert!(matches!(result, Err(Error::InvalidRegistrationService(_))));
+    }
+
+    #[tokio::test]
+    async fn should_not_fetch_prefetched_registration_info() {
+        let mut client = MockEvmcClient::new();
+        client.expect_chain_id().times(1).returning(|| Ok(CHAIN_ID));
+        client.expect_registration_info().never();
+
+        let service = RegistrationService::builder()
+            .evmc(client)
+            .target_canister(register_canister_id())
+            .wallet(Wallet::new(&mut rand::thread_rng()))
+            .registration_info(registration_info())
+            .build()
+            .await
+            .unwrap();
+
+        assert_eq!(service.chain_id, CHAIN_ID);
+    }
+
+    #[tokio::test]
+    async fn should_not_register_if_already_registered() {
+        let mut client = mock_client(0);
+        client
+            .expect_is_address_registered()
+            .returning(|_, _| Ok(true));
+        client.expect_mint_native_tokens().never();
+        client.expect_register_ic_agent().never();
+        client.expect_verify_registration().never();
+
+        let result = registration_service(client, Some(1_000_000))
+            .await
+            .register()
+            .await;
+
+        // the error carries the canister to register, not the identity principal
+        match result {
+            Err(Error::AlreadyRegistered(principal)) => {
+                assert_eq!(principal, register_canister_id())
+            }
+            result => panic!("unexpected result: {result:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn should_check_registration_before_building_the_transaction() {
+        let mut client = MockEvmcClient::new();
+        client
+            .expect_principal()
+            .returning(|| Ok(identity_principal()));
+        client.expect_chain_id().returning(|| Ok(CHAIN_ID));
+        client
+            .expect_registration_info()
+            .returning(|| Ok(registration_info()));
+        client
+            .expect_is_address_registered()
+            .returning(|_, _| Ok(true));
+        client.expect_account_basic().never();
+        client.expect_estimate_gas().never();
+        client.expect_min_gas_price().never();
+
+        let result = RegistrationService::builder()
+            .evmc(client)
+            .target_canister(register_canister_id())
+            .wallet(Wallet::new(&mut rand::thread_rng()))
+            .build()
+            .await
+            .unwrap()
+            .register()
+            .await;
+
+        assert!(matches!(result, Err(Error::AlreadyRegistered(_))));
+    }
+
+    /// Registration transaction signed by `wallet` with the fee and the gas of the mocks
+    async fn signed_transaction(
+        wallet: &Wallet<'_, SigningKey>,
+        nonce: u64,
+    ) -> RegistrationTransaction {
+        sign_registration_transaction(
+            wallet,
+            registration_info().minter_address.into(),
+            REGISTRATION_FEE,
+            nonce.into(),
+            CHAIN_ID,
+            TransactionGas {
+                gas: 21000.into(),
+                fees: TransactionFees::Legacy {
+                    gas_price: GAS_PRICE.into(),
+                },
+            },
+        )
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn should_submit_registration_without_the_signing_key() {
+        let wallet = Wallet::new(&mut rand::thread_rng());
+        let tx = signed_transaction(&wallet, 0).await;
+        let mut client = mock_client(1_000_000);
+        client
+            .expect_is_address_registered()
+            .with(eq(H160::from(wallet.address())), always())
+            .returning(|_, _| Ok(false));
+        client
+            .expect_register_ic_agent()
+            .times(1)
+            .returning(|_, _| Ok(()));
+        client.expect_verify_registration().never();
+
+        let service = RegistrationService::builder()
+            .evmc(client)
+            .target_canister(register_canister_id())
+            .address(wallet.address())
+            .build()
+            .await
+            .unwrap();
+
+        service.submit_registration(tx).await.unwrap();
+        assert!(matches!(
+            service.verify_registration().await,
+            Err(Error::InvalidRegistrationService(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn should_reject_a_stale_transaction_before_minting() {
+        let wallet = Wallet::new(&mut rand::thread_rng());
+        // the nonce of the mock account is 0
+        let tx = signed_transaction(&wallet, 1).await;
+        let mut client = mock_client(0);
+        client
+            .expect_is_address_registered()
+            .returning(|_, _| Ok(false));
+        client.expect_mint_native_tokens().never();
+        client.expect_register_ic_agent().never();
+
+        let result = RegistrationService::builder()
+            .evmc(client)
+            .target_canister(register_canister_id())
+            .address(wallet.address())
+            .amount_to_mint(Some(1_000_000))
+            .build()
+            .await
+            .unwrap()
+            .submit_registration(tx)
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(Error::InvalidRegistrationTransaction(_))
+        ));
+    }
+
+    /// Progress record of `service`, in a directory named after `test`, completed up to `step`
+    fn progress(
+        service: &RegistrationService<'_, MockEvmcClient>,
+        test: &str,
+        step: RegistrationStep,
+    ) -> RegistrationProgress {
+        let dir = std::env::temp_dir().join(format!("register_evm_agent_{test}"));
+        let mut progress = RegistrationProgress::load(
+            &dir,
+            Principal::management_canister(),
+            register_canister_id(),
+            service.address,
+        )
+        .unwrap();
+        progress.set_step(step).unwrap();
+        progress
+    }
+
+    #[tokio::test]
+    async fn should_not_resume_registration_found_on_the_evmc() {
+        let mut client = mock_client(0);
+        client
+            .expect_is_address_registered()
+            .returning(|_, _| Ok(true));
+        client.expect_register_ic_agent().never();
+        client.expect_verify_registration().never();
+        let service = registration_service(client, None).await;
+        let mut progress = progress(&service, "resume_registered", RegistrationStep::Verified);
+
+        let result = service.resume_registration(&mut progress).await;
+        std::fs::remove_file(progress.path()).unwrap();
+
+        assert!(matches!(result, Err(Error::AlreadyRegistered(_))));
+    }
+
+    #[tokio::test]
+    async fn should_restart_registration_unknown_to_the_evmc() {
+        let mut client = mock_client(1_000_000);
+        client
+            .expect_is_address_registered()
+            .returning(|_, _| Ok(false));
+        client
+            .expect_register_ic_agent()
+            .times(1)
+            .returning(|_, _| Ok(()));
+        client
+            .expect_verify_registration()
+            .times(1)
+            .returning(|_, _| Ok(()));
+        let service = registration_service(client, None).await;
+        let mut progress = progress(&service, "resume_stale", RegistrationStep::Verified);
+
+        let result = service.resume_registration(&mut progress).await;
+        std::fs::remove_file(progress.path()).unwrap();
+
+        result.unwrap();
+        assert_eq!(progress.step(), RegistrationStep::Verified);
+    }
+
+    #[tokio::test]
+    async fn should_mint_shortfall_then_register() {
+        let cost = REGISTRATION_FEE + 21000 * GAS_PRICE;
+        let balance = 10_000;
+        let mut sequence = Sequence::new();
+
+        let mut client = mock_client(balance);
+        client
+            .expect_is_address_registered()
+            .returning(|_, _| Ok(false));
+        client
+            .expect_mint_native_tokens()
+            .withf(move |_, amount| *amount == U256::from(cost - balance))
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(move |_, _| Ok(cost.into()));
+        client
+            .expect_register_ic_agent()
+            .with(always(), eq(register_canister_id()))
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(|_, _| Ok(()));
+        client
+            .expect_verify_registration()
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(|_, _| Ok(()));
+
+        registration_service(client, Some(1_000_000))
+            .await
+            .register()
+            .await
+            .unwrap();
+    }
+
+    /// Mock of a registration whose first mint times out, and covers the cost of the
+    /// registration only if `applied`; returns the number of mints sent along with the mock
+    fn mock_client_with_mint_timeout(applied: bool) -> (MockEvmcClient, Arc<AtomicU32>) {
+        let cost = REGISTRATION_FEE + 21000 * GAS_PRICE;
+        let mints = Arc::new(AtomicU32::new(0));
+        let balance = Arc::new(AtomicU64::new(0));
+
+        let mut client = MockEvmcClient::new();
+        client
+            .expect_principal()
+            .returning(|| Ok(identity_principal()));
+        client.expect_chain_id().returning(|| Ok(CHAIN_ID));
+        client
+            .expect_registration_info()
+            .returning(|| Ok(registration_info()));
+        let account_balance = balance.clone();
+        client.expect_account_basic().returning(move |_| {
+            Ok(BasicAccount {
+                balance: account_balance.load(Ordering::SeqCst).into(),
+                nonce: U256::zero(),
+            })
+        });
+        client
+            .expect_is_address_registered()
+            .returning(|_, _| Ok(false));
+        let sent = mints.clone();
+        client.expect_mint_native_tokens().returning(move |_, _| {
+            if sent.fetch_add(1, Ordering::SeqCst) > 0 {
+                balance.store(cost, Ordering::SeqCst);
+                return Ok(cost.into());
+            }
+            if applied {
+                balance.store(cost, Ordering::SeqCst);
+            }
+            Err(Error::Timeout("mint".to_string(), Duration::ZERO))
+        });
+        client.expect_register_ic_agent().returning(|_, _| Ok(()));
+        client.expect_verify_registration().returning(|_, _| Ok(()));
+
+        (client, mints)
+    }
+
+    fn retry_policy() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 3,
+            initial_backoff: Duration::ZERO,
+            max_backoff: Duration::ZERO,
+            timeout: None,
+            ingress_expiry: Duration::ZERO,
+        }
+    }
+
+    #[tokio::test]
+    async fn should_not_mint_again_after_a_timeout_if_the_mint_was_applied() {
+        let (client, mints) = mock_client_with_mint_timeout(true);
+
+        registration_service_builder(client, Some(1_000_000))
+            .retry_policy(retry_policy())
+            .build()
+            .await
+            .unwrap()
+            .register()
+            .await
+            .unwrap();
+
+        assert_eq!(mints.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn should_mint_again_after_a_timeout_once_the_mint_expired() {
+        let (client, mints) = mock_client_with_mint_timeout(false);
+
+        registration_service_builder(client, Some(1_000_000))
+            .retry_policy(retry_policy())
+            .build()
+            .await
+            .unwrap()
+            .register()
+            .await
+            .unwrap();
+
+        assert_eq!(mints.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn should_not_mint_more_than_amount_to_mint() {
+        let mut client = mock_client(0);
+        client
+            .expect_is_address_registered()
+            .returning(|_, _| Ok(false));
+        client.expect_mint_native_tokens().never();
+        client.expect_register_ic_agent().never();
+
+        let result = registration_service(client, Some(1)).await.register().await;
+
+        assert!(matches!(result, Err(Error::InsufficientFunds { .. })));
+    }
+
+    #[tokio::test]
+    async fn should_return_verification_failure() {
+        let mut client = mock_client(1_000_000);
+        client
+            .expect_is_address_registered()
+            .returning(|_, _| Ok(false));
+        client.expect_mint_native_tokens().never();
+        client
+            .expect_register_ic_agent()
+            .times(1)
+            .returning(|_, _| Ok(()));
+        client
+            .expect_verify_registration()
+            .times(1)
+            .returning(|_, _| Err(Error::Evm(EvmError::RegistrationCheckFailed)));
+
+        let result = registration_service(client, None).await.register().await;
+
+        assert!(matches!(
+            result,
+            Err(Error::Evm(EvmError::RegistrationCheckFailed))
+        ));
+    }
+
+    #[tokio::test]
+    async fn should_notify_observer_of_every_step() {
+        let cost = REGISTRATION_FEE + 21000 * GAS_PRICE;
+        let balance = 10_000;
+        let mut client = mock_client(balance);
+        client
+            .expect_is_address_registered()
+            .returning(|_, _| Ok(false));
+        client
+            .expect_mint_native_tokens()
+            .returning(move |_, _| Ok(cost.into()));
+        client.expect_register_ic_agent().returning(|_, _| Ok(()));
+        client.expect_verify_registration().returning(|_, _| Ok(()));
+        let observer = Arc::new(RecordingObserver::default());
+
+        registration_service_builder(client, Some(1_000_000))
+            .observer(observer.clone())
+            .build()
+            .await
+            .unwrap()
+            .register()
+            .await
+            .unwrap();
+
+        assert_eq!(
+            observer.events(),
+            vec![
+                format!("info_fetched {REGISTRATION_FEE}"),
+                "nonce_fetched 0".to_string(),
+                "transaction_signed".to_string(),
+                format!("tokens_minted {}", cost - balance),
+                "agent_registered".to_string(),
+                "registration_verified".to_string(),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn should_notify_observer_of_failed_step() {
+        let mut client = mock_client(1_000_000);
+        client
+            .expect_is_address_registered()
+            .returning(|_, _| Ok(false));
+        client.expect_register_ic_agent().returning(|_, _| Ok(()));
+        client
+            .expect_verify_registration()
+            .returning(|_, _| Err(Error::Evm(EvmError::RegistrationCheckFailed)));
+        let observer = Arc::new(RecordingObserver::default());
+
+        let result = registration_service_builder(client, None)
+            .observer(observer.clone())
+            .build()
+            .await
+            .unwrap()
+            .register()
+            .await;
+
+        assert!(result.is_err());
+        assert_eq!(
+            observer.events().last().unwrap(),
+            "step_failed VerifyRegistration"
+        );
+        assert!(!observer
+            .events()
+            .contains(&"registration_verified".to_string()));
+    }
+
+    #[tokio::test]
+    async fn should_sign_eip1559_registration_transaction() {
+        let wallet = Wallet::new(&mut rand::thread_rng());
+        let minter_address = "0xb0e5863d0ddf7e105e409fee0ecc0123a362e14b"
+            .parse()
+            .unwrap();
+        let gas = TransactionGas {
+            gas: 21000.into(),
+            fees: TransactionFees::Eip1559 {
+                max_fee_per_gas: 20.into(),
+                max_priority_fee_per_gas: 2.into(),
+            },
+        };
+
+        let tx =
+            sign_registration_transaction(&wallet, minter_address, 100_000, 0.into(), 355113, gas)
+                .await
+                .unwrap();
+
+        assert_eq!(tx.transaction.transaction_type, Some(2.into()));
+        assert_eq!(tx.transaction.gas, 21000.into());
+        assert_eq!(tx.transaction.max_fee_per_gas, Some(20.into()));
+        assert_eq!(tx.transaction.max_priority_fee_per_gas, Some(2.into()));
+        assert_eq!(tx.transaction.from, wallet.address());
+
+        assert_eq!(tx.cost(), (100_000 + 21000 * 20).into());
+
+        let evmc_tx = evmc_transaction(tx.transaction);
+        assert_eq!(
+            evmc_tx.max_fee_per_gas,
+            Some(ethers_core::types::U256::from(20).into())
+        );
+    }
+
+    /// Signer which fails to sign
+    struct FailingSigner;
+
+    #[async_trait]
+    impl TransactionSigner for FailingSigner {
+        fn signer_address(&self) -> Address {
+            Address::zero()
+        }
+
+        async fn sign(&self, _tx: &TypedTransaction) -> std::result::Result<Signature, String> {
+            Err("signer unavailable".to_string())
+        }
+    }
+
+    /// Signer which signs with a wallet other than the one it reports
+    struct ForeignSigner<'a> {
+        address: Address,
+        wallet: Wallet<'a, SigningKey>,
+    }
+
+    #[async_trait]
+    impl TransactionSigner for ForeignSigner<'_> {
+        fn signer_address(&self) -> Address {
+            self.address
         }
+
+        async fn sign(&self, tx: &TypedTransaction) -> std::result::Result<Signature, String> {
+            self.wallet.sign(tx).await
+        }
+    }
+
+    #[tokio::test]
+    async fn should_return_error_if_signing_fails() {
+        let minter_address = "0xb0e5863d0ddf7e105e409fee0ecc0123a362e14b"
+            .parse()
+            .unwrap();
+
+        let result = sign_registration_transaction(
+            &FailingSigner,
+            minter_address,
+            100_000,
+            0.into(),
+            355113,
+            TransactionGas::default(),
+        )
+        .await;
+
+        assert!(matches!(result, Err(Error::Signing(reason)) if reason == "signer unavailable"));
+    }
+
+    #[tokio::test]
+    async fn should_return_error_if_sender_is_not_the_signer() {
+        let minter_address = "0xb0e5863d0ddf7e105e409fee0ecc0123a362e14b"
+            .parse()
+            .unwrap();
+        let signer = ForeignSigner {
+            address: Wallet::new(&mut rand::thread_rng()).address(),
+            wallet: Wallet::new(&mut rand::thread_rng()),
+        };
+
+        let result = sign_registration_transaction(
+            &signer,
+            minter_address,
+            100_000,
+            0.into(),
+            355113,
+            TransactionGas::default(),
+        )
+        .await;
+
+        assert!(matches!(result, Err(Error::TransactionSignature(_))));
     }
 }
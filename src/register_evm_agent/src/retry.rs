@@ -0,0 +1,261 @@
+use std::future::Future;
+use std::time::Duration;
+
+use ic_agent::AgentError;
+use tokio::time::Instant;
+
+use crate::error::{Error, Result};
+
+/// Retry policy of the calls to the EVMC
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// maximum number of attempts of a call, including the first one
+    pub max_attempts: u32,
+    /// delay before the first retry; it doubles at every retry
+    pub initial_backoff: Duration,
+    /// maximum delay between two attempts
+    pub max_backoff: Duration,
+    /// timeout of a single attempt
+    pub timeout: Option<Duration>,
+    /// time during which an update sent to the replica may still be executed;
+    /// an update whose outcome is unknown is not sent again before it has passed
+    pub ingress_expiry: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(10),
+            timeout: None,
+            ingress_expiry: DEFAULT_INGRESS_EXPIRY,
+        }
+    }
+}
+
+/// Ingress expiry of the update calls sent by the IC agent, unless overridden
+pub const DEFAULT_INGRESS_EXPIRY: Duration = Duration::from_secs(5 * 60);
+
+impl RetryPolicy {
+    /// Returns the delay before the retry following `attempt`
+    fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+
+    /// Runs `call` until it succeeds, it fails with an error which is not transient
+    /// or the attempts are exhausted. `call` receives the attempt number, starting from 1,
+    /// so that updates can check the state left by a previous attempt before running again
+    pub async fn run<T, F, Fut>(&self, name: &str, mut call: F) -> Result<T>
+    where
+        F: FnMut(u32) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut attempt = 1;
+        loop {
+            match self.attempt(name, call(attempt)).await {
+                Err(err) if attempt < self.max_attempts && is_transient(&err) => {
+                    let backoff = self.backoff(attempt);
+                    warn!("{name} failed (attempt {attempt}): {err}; retrying in {backoff:?}");
+                    tokio::time::sleep(backoff).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Runs the update `call` like `run`, but never sends it again while a failed attempt
+    /// may still be executed by the replica: a transient error doesn't tell whether the update
+    /// was applied. After such an error `applied` is polled, returning the result of the update
+    /// if it finds it applied, until the ingress expiry of the attempt has passed
+    pub async fn run_update<T, F, Fut, A, AFut>(
+        &self,
+        name: &str,
+        mut call: F,
+        mut applied: A,
+    ) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+        A: FnMut() -> AFut,
+        AFut: Future<Output = Result<Option<T>>>,
+    {
+        let mut attempt = 1;
+        loop {
+            let expiry = Instant::now() + self.ingress_expiry;
+            let err = match self.attempt(name, call()).await {
+                Err(err) if attempt < self.max_attempts && is_transient(&err) => err,
+                result => return result,
+            };
+            warn!("{name} failed (attempt {attempt}): {err}; waiting for its outcome");
+
+            let mut poll = 1;
+            loop {
+                tokio::time::sleep(self.backoff(poll)).await;
+                if let Some(value) = self.run(name, |_| applied()).await? {
+                    info!("{name} applied by a previous attempt");
+                    return Ok(value);
+                }
+                if Instant::now() >= expiry {
+                    break;
+                }
+                poll += 1;
+            }
+            attempt += 1;
+        }
+    }
+
+    /// Runs a single attempt of a call, within the timeout
+    async fn attempt<T>(&self, name: &str, call: impl Future<Output = Result<T>>) -> Result<T> {
+        match self.timeout {
+            Some(timeout) => tokio::time::timeout(timeout, call)
+                .await
+                .unwrap_or_else(|_| Err(Error::Timeout(name.to_string(), timeout))),
+            None => call.await,
+        }
+    }
+}
+
+/// Whether the error is caused by the transport and the call may succeed if retried.
+/// Replica rejects are never transient
+pub fn is_transient(err: &Error) -> bool {
+    match err {
+        Error::Timeout(_, _) => true,
+        Error::Agent(err) => match err {
+            AgentError::TransportError(_) | AgentError::TimeoutWaitingForResponse { .. } => true,
+            AgentError::HttpError(payload) => payload.status == 429 || payload.status >= 500,
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use candid::Principal;
+
+    use super::*;
+
+    fn policy() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 3,
+            initial_backoff: Duration::ZERO,
+            max_backoff: Duration::ZERO,
+            timeout: None,
+            ingress_expiry: Duration::ZERO,
+        }
+    }
+
+    #[tokio::test]
+    async fn should_retry_transient_errors() {
+        let attempts = AtomicU32::new(0);
+
+        let result = policy()
+            .run("call", |attempt| {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                async move {
+                    if attempt < 3 {
+                        Err(Error::Timeout("call".to_string(), Duration::ZERO))
+                    } else {
+                        Ok(attempt)
+                    }
+                }
+            })
+            .await;
+
+        assert_eq!(result.unwrap(), 3);
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn should_not_retry_other_errors() {
+        let attempts = AtomicU32::new(0);
+
+        let result: Result<()> = policy()
+            .run("call", |_| {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                async { Err(Error::AlreadyRegistered(Principal::anonymous())) }
+            })
+            .await;
+
+        assert!(matches!(result, Err(Error::AlreadyRegistered(_))));
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn should_not_send_an_update_again_before_its_expiry() {
+        let policy = RetryPolicy {
+            initial_backoff: Duration::from_millis(10),
+            max_backoff: Duration::from_millis(10),
+            ingress_expiry: Duration::from_millis(50),
+            ..policy()
+        };
+        let attempts = AtomicU32::new(0);
+        let polls = AtomicU32::new(0);
+        let start = Instant::now();
+
+        let result = policy
+            .run_update(
+                "update",
+                || {
+                    let attempt = attempts.fetch_add(1, Ordering::SeqCst) + 1;
+                    async move {
+                        if attempt == 1 {
+                            Err(Error::Timeout("update".to_string(), Duration::ZERO))
+                        } else {
+                            Ok(attempt)
+                        }
+                    }
+                },
+                || {
+                    polls.fetch_add(1, Ordering::SeqCst);
+                    async { Ok(None) }
+                },
+            )
+            .await;
+
+        assert_eq!(result.unwrap(), 2);
+        assert!(polls.load(Ordering::SeqCst) >= 1);
+        assert!(start.elapsed() >= Duration::from_millis(50));
+    }
+
+    #[tokio::test]
+    async fn should_return_an_update_applied_by_a_failed_attempt() {
+        let attempts = AtomicU32::new(0);
+
+        let result = policy()
+            .run_update(
+                "update",
+                || {
+                    attempts.fetch_add(1, Ordering::SeqCst);
+                    async { Err(Error::Timeout("update".to_string(), Duration::ZERO)) }
+                },
+                || async { Ok(Some(42)) },
+            )
+            .await;
+
+        assert_eq!(result.unwrap(), 42);
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn should_double_backoff_up_to_max() {
+        let policy = RetryPolicy {
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(5),
+            ..Default::default()
+        };
+
+        assert_eq!(policy.backoff(1), Duration::from_secs(1));
+        assert_eq!(policy.backoff(2), Duration::from_secs(2));
+        assert_eq!(policy.backoff(3), Duration::from_secs(4));
+        assert_eq!(policy.backoff(4), Duration::from_secs(5));
+    }
+}
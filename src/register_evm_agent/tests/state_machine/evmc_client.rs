@@ -0,0 +1,119 @@
+use async_trait::async_trait;
+use candid::utils::ArgumentEncoder;
+use candid::{CandidType, Deserialize, Principal};
+use ethers_core::types::Address;
+use evmc_did::registration_info::RegistrationInfo;
+use evmc_did::{BasicAccount, Transaction, H160, U256};
+use ic_agent::AgentError;
+use ic_exports::ic_test_state_machine::CallError;
+use register_evm_agent::evmc::methods::{
+    CallMode, EvmcMethod, ACCOUNT_BASIC, ETH_CHAIN_ID, ETH_ESTIMATE_GAS, GET_MIN_GAS_PRICE,
+    IS_ADDRESS_REGISTERED, MINT_EVM_TOKENS, REGISTER_IC_AGENT, REGISTRATION_IC_AGENT_INFO,
+    VERIFY_REGISTRATION,
+};
+use register_evm_agent::evmc::EvmcClient;
+use register_evm_agent::gas::parse_quantity;
+use register_evm_agent::{Error, Result};
+
+use crate::harness::TestEnv;
+
+/// `EvmcClient` calling the mock EVMC on the state machine
+pub struct StateMachineEvmcClient {
+    env: TestEnv,
+    sender: Principal,
+}
+
+impl StateMachineEvmcClient {
+    pub fn new(env: TestEnv, sender: Principal) -> Self {
+        Self { env, sender }
+    }
+
+    /// Calls `method` as a query or as an update, according to its mode
+    fn call<A, R>(&self, method: &EvmcMethod<A, R>, args: A) -> Result<R>
+    where
+        A: ArgumentEncoder,
+        R: CandidType + for<'de> Deserialize<'de>,
+    {
+        let result = match method.mode {
+            CallMode::Query => self
+                .env
+                .query(self.env.evmc, self.sender, method.name, args),
+            CallMode::Update => self
+                .env
+                .update(self.env.evmc, self.sender, method.name, args),
+        };
+
+        result.map_err(call_error)
+    }
+}
+
+fn call_error(error: CallError) -> Error {
+    Error::Agent(AgentError::MessageError(format!("{error:?}")))
+}
+
+#[async_trait]
+impl EvmcClient for StateMachineEvmcClient {
+    fn principal(&self) -> Result<Principal> {
+        Ok(self.sender)
+    }
+
+    async fn chain_id(&self) -> Result<u64> {
+        self.call(&ETH_CHAIN_ID, ())
+    }
+
+    async fn registration_info(&self) -> Result<RegistrationInfo> {
+        self.call(&REGISTRATION_IC_AGENT_INFO, ())
+    }
+
+    async fn account_basic(&self, address: H160) -> Result<BasicAccount> {
+        self.call(&ACCOUNT_BASIC, (address,))
+    }
+
+    async fn is_address_registered(&self, address: H160, principal: Principal) -> Result<bool> {
+        self.call(&IS_ADDRESS_REGISTERED, (address, principal))
+    }
+
+    async fn register_ic_agent(
+        &self,
+        transaction: Transaction,
+        principal: Principal,
+    ) -> Result<()> {
+        Ok(self.call(&REGISTER_IC_AGENT, (transaction, principal))??)
+    }
+
+    async fn verify_registration(&self, signing_key: Vec<u8>, principal: Principal) -> Result<()> {
+        Ok(self.call(&VERIFY_REGISTRATION, (signing_key, principal))??)
+    }
+
+    async fn mint_native_tokens(&self, address: H160, amount: U256) -> Result<U256> {
+        Ok(self.call(&MINT_EVM_TOKENS, (address, amount))??)
+    }
+
+    async fn min_gas_price(&self) -> Result<ethers_core::types::U256> {
+        let gas_price = self.call(&GET_MIN_GAS_PRICE, ())?;
+
+        parse_quantity(&gas_price).map_err(Error::CouldNotGetGasPrice)
+    }
+
+    async fn estimate_gas(
+        &self,
+        from: Address,
+        to: Address,
+        value: ethers_core::types::U256,
+    ) -> Result<ethers_core::types::U256> {
+        let gas = self
+            .call(
+                &ETH_ESTIMATE_GAS,
+                (
+                    H160::from(from).to_hex_str(),
+                    Some(H160::from(to).to_hex_str()),
+                    30_000_000u64,
+                    format!("{value:#x}"),
+                    String::from("0x"),
+                ),
+            )?
+            .map_err(|e| Error::CouldNotEstimateGas(e.to_string()))?;
+
+        parse_quantity(&gas).map_err(Error::CouldNotEstimateGas)
+    }
+}
@@ -0,0 +1,163 @@
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use candid::utils::ArgumentEncoder;
+use candid::{CandidType, Deserialize, Encode, Principal};
+use eth_signer::Wallet;
+use ethers_core::k256::ecdsa::SigningKey;
+use ethers_core::types::Address;
+use evmc_did::{H160, U256};
+use ic_exports::ic_test_state_machine::{
+    call_candid_as, get_ic_test_state_machine_client_path, query_candid_as, CallError, StateMachine,
+};
+
+use crate::evmc_client::StateMachineEvmcClient;
+
+pub const CHAIN_ID: u64 = 355113;
+pub const REGISTRATION_FEE: u64 = 100_000;
+pub const MIN_GAS_PRICE: u64 = 10;
+
+/// Owner of simple_canister, and identity registering canisters with `RegistrationService`
+pub fn owner() -> Principal {
+    Principal::from_slice(&[1; 29])
+}
+
+/// Principal with no permission on the canisters
+pub fn alice() -> Principal {
+    Principal::from_slice(&[2; 29])
+}
+
+pub fn minter_address() -> Address {
+    "0xb0e5863d0ddf7e105e409fee0ecc0123a362e14b"
+        .parse()
+        .unwrap()
+}
+
+pub fn new_wallet() -> Wallet<'static, SigningKey> {
+    Wallet::new(&mut rand::thread_rng())
+}
+
+#[derive(CandidType)]
+struct MockEvmcInitData {
+    chain_id: u64,
+    minter_address: H160,
+    registration_fee: u64,
+    min_gas_price: U256,
+}
+
+#[derive(CandidType)]
+struct SimpleCanisterInitData {
+    owner: Principal,
+    evmc: Principal,
+}
+
+/// Error returned by simple_canister
+#[derive(Debug, CandidType, Deserialize, PartialEq, Eq)]
+pub enum SimpleCanisterError {
+    Internal(String),
+    NotAuthorized,
+    StableError(String),
+}
+
+/// State machine running the mock EVMC and simple_canister, owned by `owner()`
+#[derive(Clone)]
+pub struct TestEnv {
+    env: Arc<Mutex<StateMachine>>,
+    pub evmc: Principal,
+    pub simple_canister: Principal,
+}
+
+impl TestEnv {
+    pub fn new() -> Self {
+        let env = StateMachine::new(
+            &get_ic_test_state_machine_client_path("../../target"),
+            false,
+        );
+
+        let evmc_init_data = MockEvmcInitData {
+            chain_id: CHAIN_ID,
+            minter_address: minter_address().into(),
+            registration_fee: REGISTRATION_FEE,
+            min_gas_price: MIN_GAS_PRICE.into(),
+        };
+        let evmc = install_canister(&env, "mock_evmc", Encode!(&evmc_init_data).unwrap());
+
+        let simple_canister_init_data = SimpleCanisterInitData {
+            owner: owner(),
+            evmc,
+        };
+        let simple_canister = install_canister(
+            &env,
+            "simple_canister",
+            Encode!(&simple_canister_init_data).unwrap(),
+        );
+
+        Self {
+            env: Arc::new(Mutex::new(env)),
+            evmc,
+            simple_canister,
+        }
+    }
+
+    /// Creates an empty canister, to be registered with `RegistrationService`
+    pub fn create_canister(&self) -> Principal {
+        self.env.lock().unwrap().create_canister(Some(owner()))
+    }
+
+    /// Calls the update method of a canister as `sender`, returning its single result
+    pub fn update<T>(
+        &self,
+        canister_id: Principal,
+        sender: Principal,
+        method: &str,
+        args: impl ArgumentEncoder,
+    ) -> Result<T, CallError>
+    where
+        T: CandidType + for<'de> Deserialize<'de>,
+    {
+        let env = self.env.lock().unwrap();
+        call_candid_as::<_, (T,)>(&env, canister_id, sender, method, args).map(|(res,)| res)
+    }
+
+    /// Calls the query method of a canister as `sender`, returning its single result
+    pub fn query<T>(
+        &self,
+        canister_id: Principal,
+        sender: Principal,
+        method: &str,
+        args: impl ArgumentEncoder,
+    ) -> Result<T, CallError>
+    where
+        T: CandidType + for<'de> Deserialize<'de>,
+    {
+        let env = self.env.lock().unwrap();
+        query_candid_as::<_, (T,)>(&env, canister_id, sender, method, args).map(|(res,)| res)
+    }
+
+    /// Client of the mock EVMC calling it as `sender`
+    pub fn evmc_client(&self, sender: Principal) -> StateMachineEvmcClient {
+        StateMachineEvmcClient::new(self.clone(), sender)
+    }
+}
+
+fn install_canister(env: &StateMachine, name: &str, args: Vec<u8>) -> Principal {
+    let canister_id = env.create_canister(Some(owner()));
+    env.add_cycles(canister_id, 10_u128.pow(12));
+    env.install_canister(canister_id, wasm(name), args, Some(owner()));
+    canister_id
+}
+
+/// Reads a wasm built by `scripts/build.sh`; `WASMS_DIR` overrides the `.artifact` directory
+fn wasm(name: &str) -> Vec<u8> {
+    let dir = std::env::var_os("WASMS_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../.artifact"));
+    let path = dir.join(format!("{name}.wasm"));
+
+    std::fs::read(&path).unwrap_or_else(|e| {
+        panic!(
+            "failed to read {}: {e}; build the wasms with scripts/build.sh",
+            path.display()
+        )
+    })
+}
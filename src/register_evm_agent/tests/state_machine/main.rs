@@ -0,0 +1,12 @@
+//! Integration tests running simple_canister and the mock EVMC on the IC state machine.
+//!
+//! The canister wasms must be built first with `scripts/build.sh`, then run
+//!
+//! ```sh
+//! cargo test -p register_evm_agent --features state-machine-tests
+//! ```
+
+mod evmc_client;
+mod harness;
+mod registration_service;
+mod simple_canister;
@@ -0,0 +1,150 @@
+use candid::Principal;
+use eth_signer::Signer;
+use evmc_did::error::{EvmError, TransactionPoolError};
+use evmc_did::H160;
+use register_evm_agent::gas::{TransactionFees, TransactionGas};
+use register_evm_agent::progress::{RegistrationProgress, RegistrationStep};
+use register_evm_agent::{sign_registration_transaction, Error, RegistrationService};
+
+use crate::evmc_client::StateMachineEvmcClient;
+use crate::harness::{
+    minter_address, new_wallet, owner, TestEnv, CHAIN_ID, MIN_GAS_PRICE, REGISTRATION_FEE,
+};
+
+const AMOUNT_TO_MINT: u64 = 1_000_000;
+
+async fn registration_service(
+    env: &TestEnv,
+    canister_id: Principal,
+    amount_to_mint: Option<u64>,
+) -> RegistrationService<'static, StateMachineEvmcClient> {
+    RegistrationService::builder()
+        .evmc(env.evmc_client(owner()))
+        .target_canister(canister_id)
+        .chain_id(CHAIN_ID)
+        .wallet(new_wallet())
+        .amount_to_mint(amount_to_mint)
+        .build()
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn should_register_canister() {
+    let env = TestEnv::new();
+    let canister_id = env.create_canister();
+    let wallet = new_wallet();
+    let address = H160::from(wallet.address());
+
+    RegistrationService::builder()
+        .evmc(env.evmc_client(owner()))
+        .target_canister(canister_id)
+        .chain_id(CHAIN_ID)
+        .wallet(wallet)
+        .amount_to_mint(Some(AMOUNT_TO_MINT))
+        .build()
+        .await
+        .unwrap()
+        .register()
+        .await
+        .unwrap();
+
+    let status = RegistrationService::status(&env.evmc_client(owner()), &canister_id, &address)
+        .await
+        .unwrap();
+    assert!(status.registered);
+    assert_eq!(status.nonce, 1u64.into());
+
+    // the fee is paid to the minter address
+    let minter_status = RegistrationService::status(
+        &env.evmc_client(owner()),
+        &canister_id,
+        &minter_address().into(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(minter_status.balance, REGISTRATION_FEE.into());
+}
+
+#[tokio::test]
+async fn should_not_register_canister_twice() {
+    let env = TestEnv::new();
+    let canister_id = env.create_canister();
+    let service = registration_service(&env, canister_id, Some(AMOUNT_TO_MINT)).await;
+
+    service.register().await.unwrap();
+
+    assert!(matches!(
+        service.register().await,
+        Err(Error::AlreadyRegistered(_))
+    ));
+}
+
+#[tokio::test]
+async fn should_not_register_without_funds() {
+    let env = TestEnv::new();
+    let canister_id = env.create_canister();
+
+    let result = registration_service(&env, canister_id, None)
+        .await
+        .register()
+        .await;
+
+    assert!(matches!(result, Err(Error::InsufficientFunds { .. })));
+}
+
+#[tokio::test]
+async fn should_resume_registration_after_nonce_mismatch() {
+    let env = TestEnv::new();
+    let canister_id = env.create_canister();
+    let wallet = new_wallet();
+    let progress_dir = std::env::temp_dir().join("register_evm_agent_state_machine_progress");
+    std::fs::create_dir_all(&progress_dir).unwrap();
+    let mut progress =
+        RegistrationProgress::load(&progress_dir, env.evmc, canister_id, wallet.address()).unwrap();
+
+    // a transaction signed by a previous attempt with a stale nonce
+    let gas = TransactionGas {
+        gas: 21000.into(),
+        fees: TransactionFees::Legacy {
+            gas_price: MIN_GAS_PRICE.into(),
+        },
+    };
+    let stale_tx = sign_registration_transaction(
+        &wallet,
+        minter_address(),
+        REGISTRATION_FEE,
+        5.into(),
+        CHAIN_ID,
+        gas,
+    )
+    .await
+    .unwrap();
+    progress
+        .set_registration_transaction(Some(&stale_tx))
+        .unwrap();
+
+    let service = RegistrationService::builder()
+        .evmc(env.evmc_client(owner()))
+        .target_canister(canister_id)
+        .chain_id(CHAIN_ID)
+        .wallet(wallet)
+        .amount_to_mint(Some(AMOUNT_TO_MINT))
+        .build()
+        .await
+        .unwrap();
+
+    let result = service.resume_registration(&mut progress).await;
+    assert!(matches!(
+        result,
+        Err(Error::Evm(EvmError::TransactionPool(
+            TransactionPoolError::InvalidNonce { .. }
+        )))
+    ));
+    assert!(progress.registration_transaction().is_none());
+
+    service.resume_registration(&mut progress).await.unwrap();
+    assert_eq!(progress.step(), RegistrationStep::Verified);
+
+    std::fs::remove_file(progress.path()).unwrap();
+}
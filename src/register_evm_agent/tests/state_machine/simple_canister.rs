@@ -0,0 +1,201 @@
+use eth_signer::{Signer, Wallet};
+use ethers_core::k256::ecdsa::SigningKey;
+use ethers_core::types::Address;
+use evmc_did::error::EvmError;
+use evmc_did::{
+    BasicAccount, Transaction, TransactionParams, TransactionReceipt, H160, H256, U256,
+};
+use register_evm_agent::gas::{TransactionFees, TransactionGas};
+use register_evm_agent::sign_registration_transaction;
+
+use crate::harness::{
+    alice, minter_address, new_wallet, owner, SimpleCanisterError, TestEnv, CHAIN_ID,
+    MIN_GAS_PRICE, REGISTRATION_FEE,
+};
+
+type CanisterResult<T> = Result<T, SimpleCanisterError>;
+
+async fn registration_transaction(wallet: &Wallet<'_, SigningKey>) -> Transaction {
+    let gas = TransactionGas {
+        gas: 21000.into(),
+        fees: TransactionFees::Legacy {
+            gas_price: MIN_GAS_PRICE.into(),
+        },
+    };
+    let tx = sign_registration_transaction(
+        wallet,
+        minter_address(),
+        REGISTRATION_FEE,
+        0.into(),
+        CHAIN_ID,
+        gas,
+    )
+    .await
+    .unwrap();
+
+    Transaction::from(tx.transaction)
+}
+
+async fn register_account(env: &TestEnv, wallet: &Wallet<'_, SigningKey>) -> CanisterResult<()> {
+    let transaction = registration_transaction(wallet).await;
+    env.update(
+        env.simple_canister,
+        owner(),
+        "register_account",
+        (transaction, wallet.signer().to_bytes().to_vec()),
+    )
+    .unwrap()
+}
+
+fn transact(env: &TestEnv, value: u64) -> CanisterResult<H256> {
+    env.update(
+        env.simple_canister,
+        owner(),
+        "transact",
+        (
+            U256::from(value),
+            H160::from(Address::repeat_byte(0xff)),
+            Vec::<u8>::new(),
+        ),
+    )
+    .unwrap()
+}
+
+fn account_basic(env: &TestEnv, address: Address) -> BasicAccount {
+    env.query(env.evmc, owner(), "account_basic", (H160::from(address),))
+        .unwrap()
+}
+
+#[tokio::test]
+async fn should_register_account_and_transact() {
+    let env = TestEnv::new();
+    let wallet = new_wallet();
+
+    register_account(&env, &wallet).await.unwrap();
+
+    let account: CanisterResult<H160> = env
+        .query(env.simple_canister, owner(), "get_account", ())
+        .unwrap();
+    assert_eq!(account, Ok(H160::from(wallet.address())));
+    let registered: bool = env
+        .query(
+            env.evmc,
+            owner(),
+            "is_address_registered",
+            (H160::from(wallet.address()), env.simple_canister),
+        )
+        .unwrap();
+    assert!(registered);
+
+    let hash = transact(&env, 1000).unwrap();
+    let transaction: Option<Transaction> = env
+        .query(env.evmc, owner(), "eth_get_transaction_by_hash", (hash,))
+        .unwrap();
+    assert_eq!(transaction.unwrap().value, U256::from(1000u64));
+
+    let hash: CanisterResult<H256> = env
+        .update(
+            env.simple_canister,
+            owner(),
+            "create_contract",
+            (U256::zero(), vec![0x60u8, 0x00], 21000u64),
+        )
+        .unwrap();
+    let receipt: Result<Option<TransactionReceipt>, EvmError> = env
+        .query(
+            env.evmc,
+            owner(),
+            "eth_get_transaction_receipt",
+            (hash.unwrap(),),
+        )
+        .unwrap();
+    assert!(receipt.unwrap().unwrap().contract_address.is_some());
+
+    assert_eq!(
+        account_basic(&env, wallet.address()).nonce,
+        U256::from(3u64)
+    );
+}
+
+#[tokio::test]
+async fn should_not_register_account_twice() {
+    let env = TestEnv::new();
+
+    register_account(&env, &new_wallet()).await.unwrap();
+
+    assert_eq!(
+        register_account(&env, &new_wallet()).await,
+        Err(SimpleCanisterError::Internal(
+            "Account already registered".to_string()
+        ))
+    );
+}
+
+#[tokio::test]
+async fn should_recover_from_nonce_mismatch() {
+    let env = TestEnv::new();
+    let wallet = new_wallet();
+    register_account(&env, &wallet).await.unwrap();
+
+    // the EVMC rejects the transaction without using its nonce, so the canister nonce
+    // gets ahead of the EVMC one
+    assert!(transact(&env, u64::MAX).is_err());
+    assert_eq!(account_basic(&env, wallet.address()).nonce, U256::one());
+
+    // the canister takes the expected nonce from the error, and the next transaction succeeds
+    assert!(transact(&env, 1000).is_err());
+    transact(&env, 1000).unwrap();
+
+    assert_eq!(
+        account_basic(&env, wallet.address()).nonce,
+        U256::from(2u64)
+    );
+}
+
+#[tokio::test]
+async fn should_reject_unauthorized_callers() {
+    let env = TestEnv::new();
+    let wallet = new_wallet();
+    register_account(&env, &wallet).await.unwrap();
+
+    let result: CanisterResult<H256> = env
+        .update(
+            env.simple_canister,
+            alice(),
+            "transact",
+            (
+                U256::from(1000u64),
+                H160::from(Address::repeat_byte(0xff)),
+                Vec::<u8>::new(),
+            ),
+        )
+        .unwrap();
+    assert_eq!(result, Err(SimpleCanisterError::NotAuthorized));
+
+    let result: CanisterResult<()> = env
+        .update(env.simple_canister, alice(), "reset_account", ())
+        .unwrap();
+    assert_eq!(result, Err(SimpleCanisterError::NotAuthorized));
+
+    // the address is registered for simple_canister, not for alice
+    let params = TransactionParams {
+        from: wallet.address().into(),
+        value: 1000u64.into(),
+        gas_limit: 21000,
+        gas_price: None,
+        nonce: U256::one(),
+    };
+    let result: Result<H256, EvmError> = env
+        .update(
+            env.evmc,
+            alice(),
+            "call_message",
+            (
+                params,
+                H160::from(Address::repeat_byte(0xff)),
+                String::new(),
+            ),
+        )
+        .unwrap();
+    assert!(matches!(result, Err(EvmError::NotAuthorized)));
+}
@@ -28,7 +28,9 @@ impl TempCanister {
             evmc: init_data.evmc,
         };
 
-        self.state.reset(settings);
+        if let Err(err) = self.state.reset(settings) {
+            ic_exports::ic_cdk::trap(&format!("failed to initialize the canister: {err}"));
+        }
     }
 
     /// Returns principal of canister owner.
@@ -44,8 +46,7 @@ impl TempCanister {
     #[update]
     pub fn set_owner(&mut self, owner: Principal) -> Result<()> {
         self.check_owner(ic::caller())?;
-        self.state.config.set_owner(owner);
-        Ok(())
+        self.state.config.set_owner(owner)
     }
 
     /// Returns principal of evm canister id.
@@ -61,8 +62,7 @@ impl TempCanister {
     #[update]
     pub fn set_evm_canister_id(&mut self, evmc_id: Principal) -> Result<()> {
         self.check_owner(ic::caller())?;
-        self.state.config.set_evmc(evmc_id);
-        Ok(())
+        self.state.config.set_evmc(evmc_id)
     }
 
     #[query]
@@ -85,6 +85,18 @@ impl TempCanister {
             .await
     }
 
+    /// Clears the registered account, so that a new address can be registered
+    /// with `register_account`.
+    ///
+    /// This method should be called only by current owner,
+    /// else `Error::NotAuthorized` will be returned.
+    #[update]
+    pub fn reset_account(&mut self) -> Result<()> {
+        self.check_owner(ic::caller())?;
+
+        self.state.evm.reset_account()
+    }
+
     #[update]
     pub async fn transact(&mut self, value: U256, to: H160, data: Vec<u8>) -> Result<H256> {
         self.check_owner(ic::caller())?;
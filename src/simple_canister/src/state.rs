@@ -4,6 +4,8 @@ use ic_exports::stable_structures::memory_manager::MemoryId;
 use config::Config;
 use evm::EvmCanisterImpl;
 
+use crate::error::Result;
+
 mod config;
 pub mod evm;
 
@@ -21,8 +23,8 @@ pub struct State {
 
 impl State {
     /// Clear the state and set initial data from settings.
-    pub fn reset(&mut self, settings: Settings) {
-        self.config.reset(settings);
+    pub fn reset(&mut self, settings: Settings) -> Result<()> {
+        self.config.reset(settings)
     }
 }
 
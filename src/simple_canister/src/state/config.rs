@@ -5,6 +5,7 @@ use candid::{CandidType, Decode, Deserialize, Encode, Principal};
 use ic_stable_structures::{StableCell, Storable};
 
 use super::Settings;
+use crate::error::Result;
 use crate::state::CONFIG_MEMORY_ID;
 
 /// Minter canister configuration.
@@ -13,16 +14,13 @@ pub struct Config {}
 
 impl Config {
     /// Clear configuration and initialize it with data from `settings`.
-    pub fn reset(&mut self, settings: Settings) {
+    pub fn reset(&mut self, settings: Settings) -> Result<()> {
         let new_data = ConfigData {
             owner: settings.owner,
             evmc: settings.evmc,
         };
-        CONFIG_CELL.with(|cell| {
-            cell.borrow_mut()
-                .set(new_data)
-                .expect("failed to update config stable memory data")
-        })
+        CONFIG_CELL.with(|cell| cell.borrow_mut().set(new_data))?;
+        Ok(())
     }
 
     /// Returns principal of canister owner.
@@ -36,19 +34,17 @@ impl Config {
     }
 
     /// Sets a new principal for canister owner.
-    pub fn set_owner(&mut self, owner: Principal) {
+    pub fn set_owner(&mut self, owner: Principal) -> Result<()> {
         let evmc = self.get_evm_canister_id();
-        CONFIG_CELL
-            .with(|cell| cell.borrow_mut().set(ConfigData { owner, evmc }))
-            .expect("failed to update config stable memory data")
+        CONFIG_CELL.with(|cell| cell.borrow_mut().set(ConfigData { owner, evmc }))?;
+        Ok(())
     }
 
     /// Sets a new principal for evm canister id.
-    pub fn set_evmc(&mut self, evmc: Principal) {
+    pub fn set_evmc(&mut self, evmc: Principal) -> Result<()> {
         let owner: Principal = self.get_owner();
-        CONFIG_CELL
-            .with(|cell| cell.borrow_mut().set(ConfigData { owner, evmc }))
-            .expect("failed to update config stable memory data")
+        CONFIG_CELL.with(|cell| cell.borrow_mut().set(ConfigData { owner, evmc }))?;
+        Ok(())
     }
 }
 
@@ -30,14 +30,11 @@ impl EvmCanisterImpl {
         State::default().config.get_evm_canister_id()
     }
 
-    fn get_nonce(&self) -> U256 {
+    fn get_nonce(&self) -> Result<U256, Error> {
         NONCE_CELL.with(|nonce| {
             let value = nonce.borrow().get().clone();
-            nonce
-                .borrow_mut()
-                .set(value.clone() + U256::one())
-                .expect("failed to update nonce");
-            value
+            nonce.borrow_mut().set(value.clone() + U256::one())?;
+            Ok(value)
         })
     }
 
@@ -57,12 +54,7 @@ impl EvmCanisterImpl {
             expected, ..
         })) = &result
         {
-            NONCE_CELL.with(|nonce| {
-                nonce
-                    .borrow_mut()
-                    .set(expected.clone())
-                    .expect("failed to update nonce");
-            });
+            NONCE_CELL.with(|nonce| nonce.borrow_mut().set(expected.clone()))?;
         }
 
         result.map_err(|e| Error::Internal(format!("transaction error: {e}")))
@@ -74,7 +66,7 @@ impl EvmCanisterImpl {
             value,
             gas_limit,
             gas_price: None,
-            nonce: self.get_nonce(),
+            nonce: self.get_nonce()?,
         })
     }
 
@@ -92,6 +84,13 @@ impl EvmCanisterImpl {
             .register_account(transaction, signing_key, self_canister_id)
             .await
     }
+
+    /// Clears the registered account, and restarts the nonce for the next one
+    pub fn reset_account(&mut self) -> Result<(), Error> {
+        Account::default().reset_registered()?;
+        NONCE_CELL.with(|nonce| nonce.borrow_mut().set(U256::one()))?;
+        Ok(())
+    }
 }
 
 /// Interface for calling EVMC methods
@@ -34,19 +34,15 @@ impl Account {
         self_canister_id: Principal,
     ) -> Result<()> {
         // check if account is alrewady registered or in process
-        if ACCOUNT_DATA_CELL.with(|account| {
-            if account.borrow().get() == &AccountState::Unregistered {
-                account
-                    .borrow_mut()
-                    .set(AccountState::RegistrationInProgress)
-                    .expect("failed to update account state");
-                false
-            } else {
-                true
+        ACCOUNT_DATA_CELL.with(|account| {
+            if account.borrow().get() != &AccountState::Unregistered {
+                return Err(Error::Internal("Account already registered".to_string()));
             }
-        }) {
-            return Err(Error::Internal("Account already registered".to_string()));
-        }
+            account
+                .borrow_mut()
+                .set(AccountState::RegistrationInProgress)?;
+            Ok(())
+        })?;
 
         let mut evm_impl = EvmCanisterImpl::default();
 
@@ -57,14 +53,10 @@ impl Account {
             .is_address_registered(address.clone(), self_canister_id)
             .await
         {
-            Err(err) => {
-                self.reset();
-                return Err(err);
-            }
+            Err(err) => return self.fail(err),
             Ok(is_registered) => {
                 if is_registered {
-                    self.reset();
-                    return Err(Error::Internal(format!(
+                    return self.fail(Error::Internal(format!(
                         "{} is already registered",
                         address.clone()
                     )));
@@ -77,8 +69,7 @@ impl Account {
             .mint_evm_tokens(address.clone(), MINT_AMOUNT.into())
             .await
         {
-            self.reset();
-            return Err(err);
+            return self.fail(err);
         }
 
         // register ic agent
@@ -86,8 +77,7 @@ impl Account {
             .register_ic_agent(transaction, self_canister_id)
             .await
         {
-            self.reset();
-            return Err(err);
+            return self.fail(err);
         }
 
         // verify the key
@@ -95,29 +85,45 @@ impl Account {
             .verify_registration(signing_key, self_canister_id)
             .await
         {
-            self.reset();
-            return Err(err);
+            return self.fail(err);
         }
 
-        ACCOUNT_DATA_CELL.with(|account| {
-            account
-                .borrow_mut()
-                .set(AccountState::Registered(address))
-                .expect("failed to update account state")
-        });
+        ACCOUNT_DATA_CELL
+            .with(|account| account.borrow_mut().set(AccountState::Registered(address)))?;
 
         Ok(())
     }
 
-    /// Set the account state as unregistered
-    pub fn reset(&mut self) {
+    /// Clears the registered account, so that a new one can be registered.
+    /// Fails if a registration is in progress
+    pub fn reset_registered(&mut self) -> Result<()> {
         ACCOUNT_DATA_CELL.with(|account| {
-            account
-                .borrow_mut()
-                .set(AccountState::Unregistered)
-                .expect("failed to update account state")
+            if account.borrow().get() == &AccountState::RegistrationInProgress {
+                return Err(Error::Internal(
+                    "Account registration in progress".to_string(),
+                ));
+            }
+            account.borrow_mut().set(AccountState::Unregistered)?;
+            Ok(())
         })
     }
+
+    /// Set the account state as unregistered
+    pub fn reset(&mut self) -> Result<()> {
+        ACCOUNT_DATA_CELL.with(|account| account.borrow_mut().set(AccountState::Unregistered))?;
+        Ok(())
+    }
+
+    /// Aborts a failed registration, setting the account state back to unregistered.
+    /// Always returns `err`, which also reports the reset failure if any
+    fn fail(&mut self, err: Error) -> Result<()> {
+        match self.reset() {
+            Ok(()) => Err(err),
+            Err(reset_err) => Err(Error::Internal(format!(
+                "{err}; failed to reset the account state: {reset_err}"
+            ))),
+        }
+    }
 }
 
 #[derive(Debug, Default, CandidType, Deserialize, PartialEq, Eq)]